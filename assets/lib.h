@@ -0,0 +1,6 @@
+#ifndef {{ ident | upper }}_H
+#define {{ ident | upper }}_H
+
+int {{ ident }}_add(int a, int b);
+
+#endif /* {{ ident | upper }}_H */
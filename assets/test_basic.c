@@ -1,3 +1,16 @@
+{% if kind == "lib" and features.hello %}
+#include "acutest.h"
+
+#include "{{ name }}/{{ name }}.h"
+
+static void test_add(void) {
+  TEST_CHECK({{ ident }}_add(1, 2) == 3);
+  TEST_CHECK({{ ident }}_add(-1, 1) == 0);
+}
+
+/* TEST_LIST of every static void test_*(void) above, from `c-init gen-tests` */
+#include "generated/test_basic.h"
+{% else %}
 #include <stdlib.h>
 
 #define ACUTEST_IMPLEMENTATION
@@ -11,19 +24,24 @@ static void test_addition(void) {
 
 static void test_tutorial(void) {
   void *mem;
+{% if std in ["c89", "gnu89"] %}
+  void *mem2;
+{% endif %}
 
   mem = malloc(10);
   TEST_CHECK(mem != NULL);
 
+{% if std in ["c89", "gnu89"] %}
+  mem2 = realloc(mem, 20);
+{% else %}
   void *mem2 = realloc(mem, 20);
+{% endif %}
   TEST_CHECK(mem2 != NULL);
   mem = mem2;
 
   free(mem);
 }
 
-TEST_LIST = {
-    {"tutorial", test_tutorial},
-    {"addition", test_addition},
-    {NULL, NULL},
-};
+/* TEST_LIST of every static void test_*(void) above, from `c-init gen-tests` */
+#include "generated/test_basic.h"
+{% endif %}
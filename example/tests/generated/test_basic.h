@@ -0,0 +1,7 @@
+/* Generated by `c-init gen-tests` from test_basic.c: do not edit. */
+
+TEST_LIST = {
+    {"addition", test_addition},
+    {"tutorial", test_tutorial},
+    {NULL, NULL},
+};
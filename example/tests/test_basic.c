@@ -22,8 +22,5 @@ static void test_tutorial(void) {
   free(mem);
 }
 
-TEST_LIST = {
-    {"tutorial", test_tutorial},
-    {"addition", test_addition},
-    {NULL, NULL},
-};
+/* TEST_LIST of every static void test_*(void) above, from `c-init gen-tests` */
+#include "generated/test_basic.h"
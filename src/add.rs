@@ -0,0 +1,240 @@
+use crate::gen_tests;
+use crate::manifest::{self, Manifest};
+use crate::plan::Plan;
+use crate::{Layout, c_ident, green, info, print_err, project_file_name, upgrade};
+use clap::{ArgAction, Subcommand, ValueEnum};
+use indoc::formatdoc;
+use std::env;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+const BUILD_FILES: &[&str] = &["Makefile", "CMakeLists.txt", "meson.build"];
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+pub enum GuardStyle {
+    Ifndef,
+    PragmaOnce,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum AddCommand {
+    /// Add a header/source/test triple to the current project
+    Module {
+        /// Module name
+        name: String,
+
+        /// Header guard style: ifndef | pragma-once
+        #[arg(long, value_enum, default_value_t = GuardStyle::Ifndef)]
+        guard: GuardStyle,
+
+        /// Overwrite existing module files
+        #[arg(short = 'f', long, action = ArgAction::SetTrue)]
+        force: bool,
+    },
+    /// Link a system library found with pkg-config, e.g. zlib or sdl2
+    Dep {
+        /// pkg-config package name
+        name: String,
+    },
+}
+
+/// Walks up from the current directory to the first one that is a c-init
+/// project: it has a manifest, or (predating manifests) a build file next to
+/// a src/ directory.
+pub fn find_project_root() -> Option<PathBuf> {
+    let current = env::current_dir().ok()?;
+    current
+        .ancestors()
+        .find(|dir| {
+            dir.join(manifest::MANIFEST).is_file()
+                || (BUILD_FILES.iter().any(|file| dir.join(file).is_file())
+                    && dir.join("src").is_dir())
+        })
+        .map(Path::to_path_buf)
+}
+
+pub fn run(command: AddCommand, color_enabled: bool) -> ExitCode {
+    match command {
+        AddCommand::Module { name, guard, force } => add_module(&name, guard, force, color_enabled),
+        AddCommand::Dep { name } => add_dep(&name, color_enabled),
+    }
+}
+
+/// Records `name` in the manifest and regenerates the build files and flags
+/// the way `c-init upgrade` does, so edits to them are kept.
+fn add_dep(name: &str, color_enabled: bool) -> ExitCode {
+    let Some(root) = manifest::find_root() else {
+        print_err(
+            &format!(
+                "no {} found here or in any parent directory (add dep needs a project generated or adopted by c-init)",
+                manifest::MANIFEST
+            ),
+            color_enabled,
+        );
+        return ExitCode::from(1);
+    };
+    let mut manifest = match Manifest::load(&root) {
+        Ok(manifest) => manifest,
+        Err(err) => {
+            print_err(&err, color_enabled);
+            return ExitCode::from(1);
+        }
+    };
+    if manifest.settings.deps.iter().any(|dep| dep == name) {
+        info(&format!("'{}' is already a dependency", name));
+        return ExitCode::SUCCESS;
+    }
+    // Rendering asks pkg-config, so a missing package stops before anything is written.
+    manifest.settings.deps.push(name.to_string());
+
+    let summary = match upgrade::regenerate(&root, &manifest, None, color_enabled) {
+        Ok(Some(summary)) => summary,
+        Ok(None) => return ExitCode::SUCCESS,
+        Err(err) => {
+            print_err(&err, color_enabled);
+            return ExitCode::from(1);
+        }
+    };
+    summary.print(color_enabled);
+    info("");
+    info(&format!(
+        "{} dependency '{}'",
+        green("Added", color_enabled),
+        name
+    ));
+    upgrade::warn_conflicts(&summary, color_enabled);
+    ExitCode::SUCCESS
+}
+
+fn add_module(name: &str, guard: GuardStyle, force: bool, color_enabled: bool) -> ExitCode {
+    let Some(root) = find_project_root() else {
+        print_err(
+            "not inside a c-init project (no manifest, or build file and src/, found)",
+            color_enabled,
+        );
+        return ExitCode::from(1);
+    };
+
+    // Projects without a manifest predate the layout choice: include/ and src/.
+    let layout = if root.join(manifest::MANIFEST).is_file() {
+        match Manifest::load(&root) {
+            Ok(manifest) => manifest.settings.layout,
+            Err(err) => {
+                print_err(&err, color_enabled);
+                return ExitCode::from(1);
+            }
+        }
+    } else {
+        Layout::default()
+    };
+
+    let name_lower = project_file_name(name);
+    let ident = c_ident(name);
+    if name_lower.is_empty() {
+        print_err("module name must not be empty", color_enabled);
+        return ExitCode::from(1);
+    }
+    let valid_name = name_lower
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || c == '_')
+        && !name_lower.starts_with(|c: char| c.is_ascii_digit());
+    if !valid_name {
+        print_err(
+            &format!(
+                "invalid module name '{}' (use letters, digits, '_' and spaces, not starting with a digit)",
+                name
+            ),
+            color_enabled,
+        );
+        return ExitCode::from(1);
+    }
+
+    let (guard_open, guard_close) = match guard {
+        GuardStyle::Ifndef => {
+            let macro_name = format!("{}_H", ident.to_ascii_uppercase());
+            (
+                format!("#ifndef {0}\n#define {0}\n", macro_name),
+                format!("\n#endif /* {} */\n", macro_name),
+            )
+        }
+        GuardStyle::PragmaOnce => ("#pragma once\n".to_string(), String::new()),
+    };
+    let header = formatdoc!(
+        r#"
+        {guard_open}
+        int {ident}_init(void);
+        {guard_close}"#,
+        guard_open = guard_open,
+        ident = ident,
+        guard_close = guard_close
+    );
+    let source = formatdoc!(
+        r#"
+        #include "{name}.h"
+
+        int {ident}_init(void) {{
+          return 0;
+        }}
+        "#,
+        name = name_lower,
+        ident = ident
+    );
+    let test = formatdoc!(
+        r#"
+        #include "acutest.h"
+
+        #include "{name}.h"
+
+        static void test_{ident}_init(void) {{
+          TEST_CHECK({ident}_init() == 0);
+        }}
+
+        /* TEST_LIST of every static void test_*(void) above, from `c-init gen-tests` */
+        #include "generated/test_{name}.h"
+        "#,
+        name = name_lower,
+        ident = ident
+    );
+
+    // The first of several source and header directories gets the module.
+    let module_file = |dirs: &[String], ext: &str| match dirs.first().map(String::as_str) {
+        None | Some(".") => format!("{}.{}", name_lower, ext),
+        Some(dir) => format!("{}/{}.{}", dir, name_lower, ext),
+    };
+    let mut plan = Plan::new(root.clone());
+    plan.file(&module_file(&layout.inc_dirs, "h"), header);
+    plan.file(&module_file(&layout.src_dirs, "c"), source);
+    if root.join("tests").is_dir() {
+        let test_name = format!("test_{}.c", name_lower);
+        let registration = gen_tests::registration(&test_name, &gen_tests::test_functions(&test));
+        plan.file(&format!("tests/{}", test_name), test);
+        plan.file(
+            &format!("{}/test_{}.h", gen_tests::DIR, name_lower),
+            registration,
+        );
+    }
+
+    if !force && let Some(existing) = plan.paths().find(|rel| root.join(rel).exists()) {
+        print_err(
+            &format!("{} already exists (use --force to overwrite)", existing),
+            color_enabled,
+        );
+        return ExitCode::from(1);
+    }
+
+    // One transaction, so a failed write leaves no half-added module behind.
+    if let Err(err) = plan.write() {
+        print_err(&err, color_enabled);
+        return ExitCode::from(1);
+    }
+
+    info(&format!(
+        "{} module '{}'",
+        green("Added", color_enabled),
+        name_lower
+    ));
+    for rel in plan.paths() {
+        info(&format!("  {}", rel));
+    }
+    ExitCode::SUCCESS
+}
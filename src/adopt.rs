@@ -0,0 +1,333 @@
+use crate::{
+    BuildOptions, Layout, OnConflict, ProjectKind, Settings, apply_plan, build_plan, config, green,
+    info, muted, plan, print_err, templates,
+};
+use clap::{ArgAction, Args};
+use std::collections::BTreeSet;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+/// Directories never scanned for sources: build output, VCS metadata, test
+/// suites (which get their own scaffold) and libraries from `c-init vendor`.
+const SKIPPED_DIRS: &[&str] = &["target", "build", "tests", "test", "deps"];
+
+#[derive(Debug, Args)]
+pub struct AdoptArgs {
+    /// Directory to adopt (defaults to the current directory)
+    path: Option<String>,
+
+    /// Project name (defaults to directory name)
+    #[arg(long)]
+    name: Option<String>,
+
+    #[command(flatten)]
+    options: BuildOptions,
+
+    /// Build a library, even if a source defines main()
+    #[arg(long, action = ArgAction::SetTrue, conflicts_with = "bin")]
+    lib: bool,
+
+    /// Build an executable, even if no source defines main()
+    #[arg(long, action = ArgAction::SetTrue)]
+    bin: bool,
+
+    /// Directory holding sources, relative to the project (repeatable; detected by default)
+    #[arg(long = "src-dir", value_name = "DIR")]
+    src_dirs: Vec<String>,
+
+    /// Directory holding headers, relative to the project (repeatable; detected by default)
+    #[arg(long = "inc-dir", value_name = "DIR")]
+    inc_dirs: Vec<String>,
+
+    /// Skip the tests scaffold
+    #[arg(long, action = ArgAction::SetTrue)]
+    no_tests: bool,
+
+    /// Existing files that would change (an old Makefile, ...): skip | overwrite | backup (default) | prompt | merge
+    #[arg(long, value_enum, value_name = "POLICY", default_value_t = OnConflict::Backup)]
+    on_conflict: OnConflict,
+}
+
+/// C files found in an existing codebase, as `/`-separated paths relative to
+/// its root.
+#[derive(Default)]
+struct Scan {
+    sources: Vec<String>,
+    headers: Vec<String>,
+}
+
+fn scan(root: &Path, dir: &Path, found: &mut Scan) -> io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        let Some(name) = path.file_name().and_then(|name| name.to_str()) else {
+            continue;
+        };
+        if path.is_dir() {
+            if !name.starts_with('.') && !SKIPPED_DIRS.contains(&name) {
+                scan(root, &path, found)?;
+            }
+            continue;
+        }
+        let Ok(rel) = path.strip_prefix(root) else {
+            continue;
+        };
+        let rel = rel
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+        if name.ends_with(".c") {
+            found.sources.push(rel);
+        } else if name.ends_with(".h") {
+            found.headers.push(rel);
+        }
+    }
+    Ok(())
+}
+
+/// Directory part of a relative path, `.` for files at the root.
+fn parent_dir(rel: &str) -> &str {
+    rel.rsplit_once('/').map_or(".", |(dir, _)| dir)
+}
+
+/// Where `#include` should search for a header: the enclosing `include`
+/// directory when there is one (so `include/foo/foo.h` is included as
+/// `foo/foo.h`), otherwise the header's own directory.
+fn include_root(header: &str) -> String {
+    let parts: Vec<&str> = header.split('/').collect();
+    match parts[..parts.len() - 1]
+        .iter()
+        .rposition(|part| *part == "include")
+    {
+        Some(index) => parts[..=index].join("/"),
+        None => parent_dir(header).to_string(),
+    }
+}
+
+/// Whether a C source defines `main()`. Line-based: a line that starts with
+/// `main(`, optionally after `int` or `void`, and is not a prototype.
+fn defines_main(source: &str) -> bool {
+    source.lines().any(|line| {
+        let line = line.trim();
+        let rest = line
+            .strip_prefix("int")
+            .or_else(|| line.strip_prefix("void"))
+            .filter(|rest| rest.starts_with(char::is_whitespace))
+            .map_or(line, str::trim_start);
+        rest.strip_prefix("main")
+            .is_some_and(|after| after.trim_start().starts_with('('))
+            && !line.ends_with(';')
+    })
+}
+
+fn normalize_dir(dir: &str) -> String {
+    let dir = dir.trim_start_matches("./").trim_end_matches('/');
+    if dir.is_empty() {
+        ".".to_string()
+    } else {
+        dir.to_string()
+    }
+}
+
+pub fn run(args: AdoptArgs, color_enabled: bool) -> ExitCode {
+    let proj_path = args.path.unwrap_or_else(|| ".".to_string());
+    let path = PathBuf::from(&proj_path);
+    if !path.is_dir() {
+        print_err(&format!("{} is not a directory", proj_path), color_enabled);
+        return ExitCode::from(1);
+    }
+    let root = match plan::absolute_root(&path) {
+        Ok(root) => root,
+        Err(err) => {
+            print_err(
+                &format!("failed to resolve {}: {}", proj_path, err),
+                color_enabled,
+            );
+            return ExitCode::from(1);
+        }
+    };
+
+    let defaults = match config::Config::load().and_then(|cfg| config::Defaults::resolve(&cfg)) {
+        Ok(defaults) => defaults,
+        Err(err) => {
+            print_err(&err, color_enabled);
+            return ExitCode::from(1);
+        }
+    };
+    let templates = match templates::Templates::load(&[]) {
+        Ok(templates) => templates,
+        Err(err) => {
+            print_err(&err, color_enabled);
+            return ExitCode::from(1);
+        }
+    };
+
+    let mut found = Scan::default();
+    if let Err(err) = scan(&root, &root, &mut found) {
+        print_err(
+            &format!("failed to scan {}: {}", proj_path, err),
+            color_enabled,
+        );
+        return ExitCode::from(1);
+    }
+
+    let src_dirs: Vec<String> = if args.src_dirs.is_empty() {
+        let dirs: BTreeSet<&str> = found.sources.iter().map(|rel| parent_dir(rel)).collect();
+        dirs.into_iter().map(str::to_string).collect()
+    } else {
+        args.src_dirs.iter().map(|dir| normalize_dir(dir)).collect()
+    };
+    let sources: Vec<&String> = found
+        .sources
+        .iter()
+        .filter(|rel| src_dirs.iter().any(|dir| dir == parent_dir(rel)))
+        .collect();
+    if sources.is_empty() {
+        print_err(
+            &format!("no C sources found in {}", proj_path),
+            color_enabled,
+        );
+        return ExitCode::from(1);
+    }
+    // Objects are named after their source file alone, so names must be
+    // unique across the source directories.
+    for (i, source) in sources.iter().enumerate() {
+        let file_name = |rel: &str| rel.rsplit('/').next().unwrap_or_default().to_string();
+        if let Some(other) = sources[i + 1..]
+            .iter()
+            .find(|other| file_name(other) == file_name(source))
+        {
+            print_err(
+                &format!(
+                    "{} and {} share a file name; pick the directories to build with --src-dir",
+                    source, other
+                ),
+                color_enabled,
+            );
+            return ExitCode::from(1);
+        }
+    }
+
+    let inc_dirs: Vec<String> = if !args.inc_dirs.is_empty() {
+        args.inc_dirs.iter().map(|dir| normalize_dir(dir)).collect()
+    } else if found.headers.is_empty() {
+        src_dirs.clone()
+    } else {
+        let dirs: BTreeSet<String> = found.headers.iter().map(|rel| include_root(rel)).collect();
+        dirs.into_iter().collect()
+    };
+
+    let mains: Vec<&String> = sources
+        .iter()
+        .copied()
+        .filter(|rel| {
+            fs::read(root.join(rel))
+                .is_ok_and(|source| defines_main(&String::from_utf8_lossy(&source)))
+        })
+        .collect();
+    let kind = if args.lib {
+        ProjectKind::Lib
+    } else if args.bin || !mains.is_empty() {
+        ProjectKind::Bin
+    } else {
+        ProjectKind::Lib
+    };
+    if kind == ProjectKind::Bin && mains.len() > 1 {
+        let names: Vec<&str> = mains.iter().map(|rel| rel.as_str()).collect();
+        print_err(
+            &format!(
+                "main() is defined in {}, but an executable has only one; pick the directories to build with --src-dir",
+                names.join(", ")
+            ),
+            color_enabled,
+        );
+        return ExitCode::from(1);
+    }
+    let entry_points = mains
+        .iter()
+        .map(|rel| {
+            let file = rel.rsplit('/').next().unwrap_or(rel);
+            file.trim_end_matches(".c").to_string()
+        })
+        .collect();
+
+    let proj_name = args.name.unwrap_or_else(|| {
+        root.file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_else(|| "project".to_string())
+    });
+    let dry_run = args.options.dry_run;
+    let settings = Settings {
+        tests: !args.no_tests,
+        layout: Layout {
+            src_dirs,
+            inc_dirs,
+            entry_points,
+        },
+        ..args
+            .options
+            .settings(proj_name.clone(), kind, &defaults, color_enabled)
+    };
+    let mut plan = match build_plan(&settings, &templates, root, color_enabled) {
+        Ok(plan) => plan,
+        Err(err) => {
+            print_err(&err, color_enabled);
+            return ExitCode::from(1);
+        }
+    };
+    // Existing code is never touched, whatever the conflict policy.
+    for rel in plan.conflicts() {
+        if rel.ends_with(".c") || rel.ends_with(".h") {
+            plan.resolve(&rel, plan::Resolution::Skip);
+        }
+    }
+    let summary = match apply_plan(
+        &mut plan,
+        args.on_conflict,
+        dry_run,
+        &mut None,
+        color_enabled,
+    ) {
+        Ok(Some(summary)) => summary,
+        Ok(None) => return ExitCode::SUCCESS,
+        Err(err) => {
+            print_err(&err, color_enabled);
+            return ExitCode::from(1);
+        }
+    };
+
+    summary.print(color_enabled);
+    info("");
+    let layout = &settings.layout;
+    info(&format!(
+        "{} '{}' at {} as {} (sources: {}; headers: {})",
+        green("Adopted", color_enabled),
+        proj_name,
+        proj_path,
+        match kind {
+            ProjectKind::Bin => "an executable",
+            ProjectKind::Lib => "a library",
+        },
+        layout.src_dirs.join(" "),
+        layout.inc_dirs.join(" ")
+    ));
+    info("");
+    info("Next steps:");
+    info(&format!(
+        "  make         {}",
+        muted("# debug build", color_enabled)
+    ));
+    if settings.tests {
+        info(&format!(
+            "  make test    {}",
+            muted("# build and run tests", color_enabled)
+        ));
+    }
+    info(&format!(
+        "  make lint    {}",
+        muted("# clang-tidy over the existing sources", color_enabled)
+    ));
+    ExitCode::SUCCESS
+}
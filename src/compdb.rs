@@ -0,0 +1,90 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// Escapes a string for use inside a JSON string literal.
+fn json_string(value: &str) -> String {
+    let mut out = String::with_capacity(value.len() + 2);
+    out.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+/// Lists the `*.c` files directly inside `root/dir`, both those on disk and
+/// those in `planned` (paths relative to `root`), sorted by name.
+fn c_sources(root: &Path, dir: &str, planned: &[&str]) -> io::Result<Vec<String>> {
+    let mut sources: Vec<String> = planned
+        .iter()
+        .filter_map(|rel| match dir {
+            "." => Some(*rel),
+            dir => rel.strip_prefix(dir)?.strip_prefix('/'),
+        })
+        .filter(|name| !name.contains('/'))
+        .map(str::to_string)
+        .collect();
+    let path = root.join(dir);
+    if path.is_dir() {
+        sources.extend(
+            fs::read_dir(path)?
+                .filter_map(|entry| entry.ok())
+                .map(|entry| entry.file_name().to_string_lossy().into_owned()),
+        );
+    }
+    sources.retain(|name| name.ends_with(".c"));
+    sources.sort();
+    sources.dedup();
+    Ok(sources)
+}
+
+fn entry(directory: &Path, file: &str, cc: &str, flags: &str) -> String {
+    let mut arguments: Vec<String> = cc.split_whitespace().map(json_string).collect();
+    arguments.extend(flags.lines().filter(|l| !l.is_empty()).map(json_string));
+    arguments.push(json_string("-c"));
+    arguments.push(json_string(file));
+    format!(
+        "  {{\"directory\": {}, \"file\": {}, \"arguments\": [{}]}}",
+        json_string(&directory.to_string_lossy()),
+        json_string(file),
+        arguments.join(", ")
+    )
+}
+
+/// Builds a compilation database for `root`, matching what `make compdb` emits:
+/// `*.c` in each of `src_dirs` compiled from the project root with
+/// `app_flags`, and `tests/*.c` compiled from `tests/` with `test_flags`.
+/// `planned` lists files that are about to be written.
+pub fn render(
+    root: &Path,
+    cc: &str,
+    app_flags: &str,
+    test_flags: Option<&str>,
+    src_dirs: &[String],
+    planned: &[&str],
+) -> io::Result<String> {
+    let mut entries = Vec::new();
+    for dir in src_dirs {
+        for source in c_sources(root, dir, planned)? {
+            entries.push(entry(root, &format!("{}/{}", dir, source), cc, app_flags));
+        }
+    }
+    if let Some(test_flags) = test_flags {
+        let tests_dir = root.join("tests");
+        for source in c_sources(root, "tests", planned)? {
+            entries.push(entry(&tests_dir, &source, cc, test_flags));
+        }
+    }
+    if entries.is_empty() {
+        return Ok("[]\n".to_string());
+    }
+    Ok(format!("[\n{}\n]\n", entries.join(",\n")))
+}
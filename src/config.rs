@@ -0,0 +1,264 @@
+use crate::{CStd, CcChoice, Strictness, TestFramework, green, info, muted, print_err, write_file};
+use clap::{Subcommand, ValueEnum};
+use std::env;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::process::ExitCode;
+use toml::{Table, Value};
+
+/// Keys understood in config.toml, with the values they accept.
+pub const KEYS: &[(&str, &str)] = &[
+    ("cc", "clang | gcc | tcc | zig | icx, or a compiler command"),
+    ("strictness", "loose | strict | strictest"),
+    ("linter_strictness", "loose | strict | strictest"),
+    (
+        "std",
+        "c89 | c99 | c11 | c17 | c23 | gnu89 | gnu99 | gnu11 | gnu17 | gnu23",
+    ),
+    ("license", "license name, e.g. MIT"),
+    ("author", "author name for the README"),
+    ("test_framework", "acutest"),
+    ("git", "true | false (run git init)"),
+    ("git_commit", "true | false (make the initial commit)"),
+];
+
+#[derive(Debug, Subcommand)]
+pub enum ConfigCommand {
+    /// Print a value from the config file
+    Get {
+        /// Config key
+        key: String,
+    },
+    /// Store a value in the config file
+    Set {
+        /// Config key
+        key: String,
+        /// New value
+        value: String,
+    },
+    /// Remove a value from the config file
+    Unset {
+        /// Config key
+        key: String,
+    },
+    /// List config values and where the file lives
+    List,
+}
+
+/// `$XDG_CONFIG_HOME/c-init`, falling back to `~/.config/c-init`.
+pub fn config_dir() -> Option<PathBuf> {
+    env::var_os("XDG_CONFIG_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+        .map(|dir| dir.join("c-init"))
+}
+
+pub fn config_path() -> Option<PathBuf> {
+    config_dir().map(|dir| dir.join("config.toml"))
+}
+
+fn env_var_name(key: &str) -> String {
+    format!("C_INIT_{}", key.to_ascii_uppercase())
+}
+
+fn parse_bool(value: &str) -> Option<bool> {
+    match value.to_ascii_lowercase().as_str() {
+        "1" | "true" | "yes" | "on" => Some(true),
+        "0" | "false" | "no" | "off" => Some(false),
+        _ => None,
+    }
+}
+
+/// Checks `value` against what `key` accepts and converts it to its TOML form.
+fn validate(key: &str, value: &str) -> Result<Value, String> {
+    let check_enum = |valid: bool| {
+        if valid {
+            Ok(())
+        } else {
+            let accepts = KEYS
+                .iter()
+                .find(|(k, _)| *k == key)
+                .map_or("", |(_, accepts)| *accepts);
+            Err(format!(
+                "invalid value '{}' for {} (expected {})",
+                value, key, accepts
+            ))
+        }
+    };
+    fn parses<T: ValueEnum>(value: &str) -> bool {
+        T::from_str(value, true).is_ok()
+    }
+    match key {
+        "cc" => CcChoice::parse(value).map(|_| Value::from(value)),
+        "strictness" | "linter_strictness" => {
+            check_enum(parses::<Strictness>(value)).map(|_| Value::from(value))
+        }
+        "std" => check_enum(parses::<CStd>(value)).map(|_| Value::from(value)),
+        "test_framework" => check_enum(parses::<TestFramework>(value)).map(|_| Value::from(value)),
+        "git" | "git_commit" => parse_bool(value)
+            .map(Value::from)
+            .ok_or_else(|| format!("expected true or false, got '{}'", value)),
+        "license" | "author" => Ok(Value::from(value)),
+        _ => Err(format!("unknown config key '{}'", key)),
+    }
+}
+
+/// Contents of the user's config.toml (empty when the file does not exist).
+pub struct Config {
+    path: Option<PathBuf>,
+    table: Table,
+}
+
+impl Config {
+    pub fn load() -> Result<Self, String> {
+        let path = config_path();
+        let table = match &path {
+            Some(path) => match fs::read_to_string(path) {
+                Ok(contents) => contents
+                    .parse::<Table>()
+                    .map_err(|err| format!("invalid config {}: {}", path.display(), err))?,
+                Err(err) if err.kind() == io::ErrorKind::NotFound => Table::new(),
+                Err(err) => return Err(format!("failed to read {}: {}", path.display(), err)),
+            },
+            None => Table::new(),
+        };
+        Ok(Self { path, table })
+    }
+
+    fn get(&self, key: &str) -> Option<String> {
+        self.table.get(key).map(|value| match value {
+            Value::String(s) => s.clone(),
+            other => other.to_string(),
+        })
+    }
+
+    fn save(&self) -> io::Result<()> {
+        let Some(path) = &self.path else {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                "neither XDG_CONFIG_HOME nor HOME is set",
+            ));
+        };
+        write_file(path, &self.table.to_string())
+    }
+}
+
+/// Defaults from the environment and config file. CLI flags take precedence
+/// over these, and built-in defaults apply to whatever is left unset.
+#[derive(Debug)]
+pub struct Defaults {
+    pub cc: Option<CcChoice>,
+    pub strictness: Option<Strictness>,
+    pub linter_strictness: Option<Strictness>,
+    pub std: Option<CStd>,
+    pub license: Option<String>,
+    pub author: Option<String>,
+    pub test_framework: Option<TestFramework>,
+    pub git: Option<bool>,
+    pub git_commit: Option<bool>,
+}
+
+impl Defaults {
+    pub fn resolve(config: &Config) -> Result<Self, String> {
+        // env var > config file
+        let lookup = |key: &str| -> Result<Option<String>, String> {
+            let from_env = env::var(env_var_name(key)).ok().filter(|v| !v.is_empty());
+            let (value, origin) = match from_env {
+                Some(value) => (value, env_var_name(key)),
+                None => match config.get(key) {
+                    Some(value) => (value, "config file".to_string()),
+                    None => return Ok(None),
+                },
+            };
+            validate(key, &value)
+                .map(|_| Some(value))
+                .map_err(|err| format!("invalid {} from {}: {}", key, origin, err))
+        };
+        fn parse_enum<T: ValueEnum>(value: Option<String>) -> Option<T> {
+            value.and_then(|v| T::from_str(&v, true).ok())
+        }
+        Ok(Self {
+            cc: lookup("cc")?.and_then(|v| CcChoice::parse(&v).ok()),
+            strictness: parse_enum(lookup("strictness")?),
+            linter_strictness: parse_enum(lookup("linter_strictness")?),
+            std: parse_enum(lookup("std")?),
+            license: lookup("license")?,
+            author: lookup("author")?,
+            test_framework: parse_enum(lookup("test_framework")?),
+            git: lookup("git")?.and_then(|v| parse_bool(&v)),
+            git_commit: lookup("git_commit")?.and_then(|v| parse_bool(&v)),
+        })
+    }
+}
+
+pub fn run(command: ConfigCommand, color_enabled: bool) -> ExitCode {
+    let mut config = match Config::load() {
+        Ok(config) => config,
+        Err(err) => {
+            print_err(&err, color_enabled);
+            return ExitCode::from(1);
+        }
+    };
+    match command {
+        ConfigCommand::Get { key } => {
+            if !KEYS.iter().any(|(k, _)| *k == key) {
+                print_err(&format!("unknown config key '{}'", key), color_enabled);
+                return ExitCode::from(1);
+            }
+            match config.get(&key) {
+                Some(value) => {
+                    info(&value);
+                    ExitCode::SUCCESS
+                }
+                None => ExitCode::from(1),
+            }
+        }
+        ConfigCommand::Set { key, value } => {
+            let value = match validate(&key, &value) {
+                Ok(value) => value,
+                Err(err) => {
+                    print_err(&err, color_enabled);
+                    return ExitCode::from(1);
+                }
+            };
+            config.table.insert(key, value);
+            if let Err(err) = config.save() {
+                print_err(&format!("failed to write config: {}", err), color_enabled);
+                return ExitCode::from(1);
+            }
+            ExitCode::SUCCESS
+        }
+        ConfigCommand::Unset { key } => {
+            if config.table.remove(&key).is_some()
+                && let Err(err) = config.save()
+            {
+                print_err(&format!("failed to write config: {}", err), color_enabled);
+                return ExitCode::from(1);
+            }
+            ExitCode::SUCCESS
+        }
+        ConfigCommand::List => {
+            if let Some(path) = &config.path {
+                info(&muted(&format!("# {}", path.display()), color_enabled));
+            }
+            for (key, accepts) in KEYS {
+                let env_name = env_var_name(key);
+                match config.get(key) {
+                    Some(value) => info(&format!("{} = {}", green(key, color_enabled), value)),
+                    None => info(&muted(&format!("# {} = <{}>", key, accepts), color_enabled)),
+                }
+                if let Ok(value) = env::var(&env_name)
+                    && !value.is_empty()
+                {
+                    info(&muted(
+                        &format!("#   overridden by {}={}", env_name, value),
+                        color_enabled,
+                    ));
+                }
+            }
+            ExitCode::SUCCESS
+        }
+    }
+}
@@ -0,0 +1,503 @@
+use crate::manifest::{self, Manifest};
+use crate::{
+    BuildSystem, CcChoice, Compiler, add, colorize, config, find_executable, green, includes, info,
+    muted, print_err, qemu_runner, target_arch,
+};
+use clap::Args;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, ExitCode, Stdio};
+
+#[derive(Debug, Args)]
+pub struct DoctorArgs {
+    /// Compiler to check (defaults to the current project's, then the configured one)
+    #[arg(long, value_name = "COMPILER", value_parser = CcChoice::parse)]
+    cc: Option<CcChoice>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Status {
+    Ok,
+    /// Missing or broken, but only an optional target suffers.
+    Warn,
+    /// The project cannot be built.
+    Fail,
+}
+
+struct Check {
+    name: String,
+    status: Status,
+    detail: String,
+}
+
+/// What the checks need to know about the project doctor runs in, if any.
+struct Project {
+    root: Option<PathBuf>,
+    build_system: BuildSystem,
+    cc: String,
+    lib: bool,
+    /// Target triple of a cross-compiled project.
+    target: Option<String>,
+    /// qemu binary `make run` and `make test` go through, when this machine
+    /// can't execute the target's binaries.
+    runner: Option<String>,
+    /// pkg-config packages the project links against.
+    deps: Vec<String>,
+    /// Programs `make coverage` runs.
+    coverage_tools: Vec<String>,
+    /// Whether `make coverage` writes its HTML report with gcovr, or lcov and
+    /// genhtml.
+    gcov_report: bool,
+}
+
+impl Project {
+    /// Why an optional tool matters: the Makefile target that runs it, or
+    /// `purpose` for projects built some other way.
+    fn needed_by(&self, target: &str, purpose: &str) -> String {
+        match self.build_system {
+            BuildSystem::Make => format!("make {} needs it", target),
+            _ => purpose.to_string(),
+        }
+    }
+}
+
+/// The `CC := ...` command from a Makefile.
+fn makefile_cc(makefile: &str) -> Option<String> {
+    makefile.lines().find_map(|line| {
+        let (name, value) = line.split_once(":=")?;
+        (name.trim() == "CC").then(|| value.trim().to_string())
+    })
+}
+
+/// The programs `make coverage` runs, from the Makefile's `GCOV ?= ...` or
+/// `LLVM_PROFDATA ?= ...` and `LLVM_COV ?= ...`.
+fn makefile_coverage_tools(makefile: &str) -> Vec<String> {
+    let mut tools = Vec::new();
+    for line in makefile.lines() {
+        let Some((name, value)) = line.split_once("?=") else {
+            continue;
+        };
+        if matches!(name.trim(), "GCOV" | "LLVM_PROFDATA" | "LLVM_COV") {
+            tools.push(value.trim().to_string());
+        }
+    }
+    tools
+}
+
+fn detect_project(cc: Option<CcChoice>) -> Result<Project, String> {
+    let root = manifest::find_root().or_else(add::find_project_root);
+    let manifest = root
+        .as_ref()
+        .filter(|root| root.join(manifest::MANIFEST).is_file())
+        .map(|root| Manifest::load(root))
+        .transpose()?;
+    let makefile = root
+        .as_ref()
+        .and_then(|root| fs::read_to_string(root.join("Makefile")).ok());
+
+    let build_system = match (&manifest, &root) {
+        (Some(manifest), _) => manifest.settings.build_system,
+        (None, Some(root)) if root.join("CMakeLists.txt").is_file() => BuildSystem::Cmake,
+        (None, Some(root)) if root.join("meson.build").is_file() => BuildSystem::Meson,
+        _ => BuildSystem::Make,
+    };
+    let defaults = config::Config::load().and_then(|cfg| config::Defaults::resolve(&cfg))?;
+    let cc = match (cc, &makefile, &manifest) {
+        (Some(cc), _, _) => cc.command(),
+        (None, Some(makefile), _) if makefile_cc(makefile).is_some() => {
+            makefile_cc(makefile).unwrap_or_default()
+        }
+        (None, _, Some(manifest)) => manifest.settings.cc_command(),
+        (None, _, None) => defaults
+            .cc
+            .unwrap_or(CcChoice::Known(Compiler::Clang))
+            .command(),
+    };
+    let lib = match (&manifest, &makefile) {
+        (Some(manifest), _) => manifest.settings.kind == crate::ProjectKind::Lib,
+        (None, Some(makefile)) => makefile.contains("LIB_STATIC"),
+        _ => false,
+    };
+    let target = manifest
+        .as_ref()
+        .and_then(|manifest| manifest.settings.target.clone());
+    let runner = manifest.as_ref().and_then(|manifest| {
+        let triple = manifest.settings.target.as_deref()?;
+        (target_arch(triple) != env::consts::ARCH).then(|| {
+            let runner = qemu_runner(triple, manifest.settings.sysroot.as_deref());
+            runner
+                .split_whitespace()
+                .next()
+                .unwrap_or_default()
+                .to_string()
+        })
+    });
+    let deps = manifest
+        .map(|manifest| manifest.settings.deps)
+        .unwrap_or_default();
+    let coverage_tools = makefile
+        .as_deref()
+        .map(makefile_coverage_tools)
+        .unwrap_or_default();
+    let gcov_report = makefile
+        .as_deref()
+        .is_some_and(|makefile| makefile.lines().any(|line| line.starts_with("GCOV ?=")));
+    Ok(Project {
+        root,
+        build_system,
+        cc,
+        lib,
+        target,
+        runner,
+        deps,
+        coverage_tools,
+        gcov_report,
+    })
+}
+
+/// First line of `<program> <args>`'s output, e.g. `GNU Make 4.3`.
+fn version(program: &Path, args: &[&str]) -> Option<String> {
+    let output = Command::new(program)
+        .args(args)
+        .stdin(Stdio::null())
+        .output()
+        .ok()?;
+    let text = if output.stdout.is_empty() {
+        output.stderr
+    } else {
+        output.stdout
+    };
+    String::from_utf8_lossy(&text)
+        .lines()
+        .map(str::trim)
+        .find(|line| !line.is_empty())
+        .map(str::to_string)
+}
+
+/// Looks up a tool on PATH. `missing` is the status to report when it isn't
+/// there, with `why` explaining what it is needed for.
+fn tool(name: &str, version_args: &[&str], missing: Status, why: &str) -> Check {
+    match find_executable(name) {
+        Some(path) => Check {
+            name: name.to_string(),
+            status: Status::Ok,
+            detail: match version(&path, version_args) {
+                Some(version) => format!("{} ({})", version, path.display()),
+                None => path.display().to_string(),
+            },
+        },
+        None => Check {
+            name: name.to_string(),
+            status: missing,
+            detail: format!("not found ({})", why),
+        },
+    }
+}
+
+/// Compiles a tiny program with `flags` and, when `run`, runs it, returning
+/// the first line of whatever went wrong.
+fn probe(cc: &str, flags: &[&str], run: bool) -> Result<(), String> {
+    const PROBE: &str = "#include <stdlib.h>\n\nint main(void) {\n  int *p = malloc(sizeof *p);\n  if (p == NULL) {\n    return 1;\n  }\n  *p = 0;\n  int r = *p;\n  free(p);\n  return r;\n}\n";
+    let dir = tempfile::tempdir().map_err(|err| err.to_string())?;
+    let source = dir.path().join("probe.c");
+    let binary = dir.path().join("probe");
+    fs::write(&source, PROBE).map_err(|err| err.to_string())?;
+    let mut words = cc.split_whitespace();
+    let program = words.next().ok_or("no compiler command")?;
+    let first_line = |bytes: &[u8]| {
+        String::from_utf8_lossy(bytes)
+            .lines()
+            .find(|line| !line.trim().is_empty())
+            .unwrap_or("no output")
+            .trim()
+            .to_string()
+    };
+    let compiled = Command::new(program)
+        .args(words)
+        .args(flags)
+        .arg(&source)
+        .arg("-o")
+        .arg(&binary)
+        .output()
+        .map_err(|err| err.to_string())?;
+    if !compiled.status.success() {
+        return Err(format!("compile failed: {}", first_line(&compiled.stderr)));
+    }
+    if !run {
+        return Ok(());
+    }
+    let ran = Command::new(&binary)
+        .output()
+        .map_err(|err| err.to_string())?;
+    if !ran.status.success() {
+        return Err(format!("probe failed: {}", first_line(&ran.stderr)));
+    }
+    Ok(())
+}
+
+pub fn run(args: DoctorArgs, color_enabled: bool) -> ExitCode {
+    let project = match detect_project(args.cc) {
+        Ok(project) => project,
+        Err(err) => {
+            print_err(&err, color_enabled);
+            return ExitCode::from(1);
+        }
+    };
+    match &project.root {
+        Some(root) => info(&format!(
+            "Checking the toolchain for {} ({})",
+            root.display(),
+            project.build_system.as_str()
+        )),
+        None => info("Checking the toolchain (not inside a project)"),
+    }
+    info("");
+
+    let mut checks = Vec::new();
+    let cc_program = project.cc.split_whitespace().next().unwrap_or_default();
+    let cc_check = tool(
+        cc_program,
+        &["--version"],
+        Status::Fail,
+        "the configured compiler",
+    );
+    let cc_found = cc_check.status == Status::Ok;
+    checks.push(cc_check);
+    if let (true, Some(triple)) = (cc_found, &project.target) {
+        // Cross binaries can't run here, so only building them is checked.
+        checks.push(match probe(&project.cc, &[], false) {
+            Ok(()) => Check {
+                name: "compile".to_string(),
+                status: Status::Ok,
+                detail: format!("{} builds a C program for {}", project.cc, triple),
+            },
+            Err(err) => Check {
+                name: "compile".to_string(),
+                status: Status::Fail,
+                detail: err,
+            },
+        });
+    } else if cc_found {
+        checks.push(match probe(&project.cc, &[], true) {
+            Ok(()) => Check {
+                name: "compile".to_string(),
+                status: Status::Ok,
+                detail: format!("{} builds and runs a C program", project.cc),
+            },
+            Err(err) => Check {
+                name: "compile".to_string(),
+                status: Status::Fail,
+                detail: err,
+            },
+        });
+        let sanitizers = probe(
+            &project.cc,
+            &["-fsanitize=address,undefined", "-fno-omit-frame-pointer"],
+            true,
+        );
+        checks.push(match sanitizers {
+            Ok(()) => Check {
+                name: "ASan/UBSan".to_string(),
+                status: Status::Ok,
+                detail: "sanitizer runtimes link and run".to_string(),
+            },
+            Err(err) => Check {
+                name: "ASan/UBSan".to_string(),
+                status: Status::Warn,
+                detail: format!(
+                    "{} ({})",
+                    err,
+                    project.needed_by("sanitize", "sanitizer builds need it")
+                ),
+            },
+        });
+    }
+
+    match project.build_system {
+        BuildSystem::Make => {
+            checks.push(tool(
+                "make",
+                &["--version"],
+                Status::Fail,
+                "builds the project",
+            ));
+            if project.lib {
+                checks.push(tool(
+                    "ar",
+                    &["--version"],
+                    Status::Fail,
+                    "archives the library",
+                ));
+            }
+        }
+        BuildSystem::Cmake => {
+            checks.push(tool(
+                "cmake",
+                &["--version"],
+                Status::Fail,
+                "builds the project",
+            ));
+        }
+        BuildSystem::Meson => {
+            checks.push(tool(
+                "meson",
+                &["--version"],
+                Status::Fail,
+                "builds the project",
+            ));
+            checks.push(tool(
+                "ninja",
+                &["--version"],
+                Status::Fail,
+                "meson's backend",
+            ));
+        }
+    }
+    if !project.deps.is_empty() {
+        let pkg_config = tool(
+            "pkg-config",
+            &["--version"],
+            Status::Fail,
+            "finds the project's libraries",
+        );
+        let pkg_config_found = pkg_config.status == Status::Ok;
+        checks.push(pkg_config);
+        for dep in project.deps.iter().filter(|_| pkg_config_found) {
+            checks.push(match includes::pkg_config(dep) {
+                Ok(_) => Check {
+                    name: dep.clone(),
+                    status: Status::Ok,
+                    detail: version(Path::new("pkg-config"), &["--modversion", dep])
+                        .unwrap_or_default(),
+                },
+                Err(err) => Check {
+                    name: dep.clone(),
+                    status: Status::Fail,
+                    detail: err,
+                },
+            });
+        }
+    }
+    if let Some(runner) = &project.runner {
+        checks.push(tool(
+            runner,
+            &["--version"],
+            Status::Warn,
+            &project.needed_by("run", "running the target's binaries"),
+        ));
+    }
+    checks.push(tool("git", &["--version"], Status::Warn, "version control"));
+    checks.push(tool(
+        "clang-tidy",
+        &["--version"],
+        Status::Warn,
+        &project.needed_by("lint", "linting"),
+    ));
+    checks.push(tool(
+        "clang-format",
+        &["--version"],
+        Status::Warn,
+        &project.needed_by("fmt", "formatting"),
+    ));
+    for coverage_tool in &project.coverage_tools {
+        checks.push(tool(
+            coverage_tool,
+            &["--version"],
+            Status::Warn,
+            &project.needed_by("coverage", "coverage reports"),
+        ));
+    }
+    // Without an HTML report, make coverage fails rather than print less.
+    if project.gcov_report {
+        let gcovr = tool("gcovr", &["--version"], Status::Fail, "");
+        let lcov = tool("lcov", &["--version"], Status::Fail, "");
+        let genhtml = tool("genhtml", &["--version"], Status::Fail, "");
+        checks.push(if gcovr.status == Status::Ok {
+            gcovr
+        } else if lcov.status == Status::Ok && genhtml.status == Status::Ok {
+            Check {
+                name: "lcov/genhtml".to_string(),
+                status: Status::Ok,
+                detail: lcov.detail,
+            }
+        } else {
+            Check {
+                name: "gcovr".to_string(),
+                status: Status::Fail,
+                detail:
+                    "not found, nor lcov and genhtml (make coverage needs one for its HTML report)"
+                        .to_string(),
+            }
+        });
+    }
+    checks.push(tool(
+        "clangd",
+        &["--version"],
+        Status::Warn,
+        "editor integration",
+    ));
+    if !project.lib && project.build_system == BuildSystem::Make {
+        let watchers = [
+            tool("entr", &[], Status::Warn, ""),
+            tool("watchexec", &["--version"], Status::Warn, ""),
+        ];
+        checks.push(
+            match watchers
+                .into_iter()
+                .find(|check| check.status == Status::Ok)
+            {
+                Some(found) => found,
+                None => Check {
+                    name: "entr/watchexec".to_string(),
+                    status: Status::Warn,
+                    detail: format!(
+                        "neither found ({})",
+                        project.needed_by("watch", "rebuilding on change")
+                    ),
+                },
+            },
+        );
+    }
+
+    let width = checks
+        .iter()
+        .map(|check| check.name.len())
+        .max()
+        .unwrap_or(0);
+    for check in &checks {
+        let mark = match check.status {
+            Status::Ok => green("✔", color_enabled),
+            Status::Warn => colorize("!", "33", color_enabled),
+            Status::Fail => colorize("✘", "31", color_enabled),
+        };
+        let detail = if check.status == Status::Ok {
+            muted(&check.detail, color_enabled)
+        } else {
+            check.detail.clone()
+        };
+        info(&format!(
+            "  {} {:width$}  {}",
+            mark,
+            check.name,
+            detail,
+            width = width
+        ));
+    }
+
+    let failures = checks
+        .iter()
+        .filter(|check| check.status == Status::Fail)
+        .count();
+    let warnings = checks
+        .iter()
+        .filter(|check| check.status == Status::Warn)
+        .count();
+    info("");
+    info(&format!("{} problem(s), {} warning(s)", failures, warnings));
+    if failures > 0 {
+        ExitCode::from(1)
+    } else {
+        ExitCode::SUCCESS
+    }
+}
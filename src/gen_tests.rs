@@ -0,0 +1,234 @@
+use crate::plan::Plan;
+use crate::{add, info, print_err, write_file};
+use clap::{ArgAction, Args};
+use std::collections::BTreeMap;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+/// Registration headers, next to the acutest tests that include them.
+pub const DIR: &str = "tests/generated";
+
+#[derive(Debug, Args)]
+pub struct GenTestsArgs {
+    /// Test sources to register (default: tests/*.c of the current project)
+    files: Vec<String>,
+
+    /// Only check that every test is registered and the headers are current
+    #[arg(long, action = ArgAction::SetTrue)]
+    check: bool,
+}
+
+/// The `#include` that hands a test file's registration to `gen-tests`.
+pub fn include_line(stem: &str) -> String {
+    format!("#include \"generated/{}.h\"", stem)
+}
+
+/// Names of the `static void test_*(void)` functions defined in `source`,
+/// in order.
+pub fn test_functions(source: &str) -> Vec<String> {
+    let mut found = Vec::new();
+    for line in source.lines() {
+        let Some(rest) = line.trim_start().strip_prefix("static void ") else {
+            continue;
+        };
+        let rest = rest.trim_start();
+        let ident_len = rest
+            .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+            .unwrap_or(rest.len());
+        let (ident, params) = rest.split_at(ident_len);
+        let params: String = params.chars().filter(|c| !c.is_whitespace()).collect();
+        if ident.starts_with("test_")
+            && params.starts_with("(void)")
+            && !params.starts_with("(void);")
+            && !found.iter().any(|known| known == ident)
+        {
+            found.push(ident.to_string());
+        }
+    }
+    found
+}
+
+/// The identifiers between `TEST_LIST` and the end of its initializer, or
+/// `None` when `source` has no hand-written list.
+fn listed(source: &str) -> Option<Vec<String>> {
+    let start = source.find("TEST_LIST")?;
+    let list = &source[start..];
+    let list = &list[..list.find("};").unwrap_or(list.len())];
+    Some(
+        list.split(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+            .filter(|word| word.starts_with("test_"))
+            .map(str::to_string)
+            .collect(),
+    )
+}
+
+/// The acutest `TEST_LIST` for `functions`, each named without its `test_`.
+pub fn registration(file_name: &str, functions: &[String]) -> String {
+    let mut out = format!(
+        "/* Generated by `c-init gen-tests` from {}: do not edit. */\n\nTEST_LIST = {{\n",
+        file_name
+    );
+    for function in functions {
+        let name = function.strip_prefix("test_").unwrap_or(function);
+        out.push_str(&format!("    {{\"{}\", {}}},\n", name, function));
+    }
+    out.push_str("    {NULL, NULL},\n};\n");
+    out
+}
+
+/// Registration headers for the tests in tests/ and those about to be
+/// written there: (path from the project root, contents). The copy on disk
+/// wins when both include their registration, so an upgrade keeps the tests
+/// added since generation.
+pub fn headers(plan: &Plan) -> Vec<(String, String)> {
+    let mut sources: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    if let Ok(entries) = fs::read_dir(plan.root().join("tests")) {
+        for path in entries.filter_map(|entry| entry.ok().map(|entry| entry.path())) {
+            if let Some(name) = path.file_name().and_then(|name| name.to_str())
+                && name.ends_with(".c")
+                && let Ok(source) = fs::read_to_string(&path)
+            {
+                sources.entry(name.to_string()).or_default().push(source);
+            }
+        }
+    }
+    for rel in plan.paths() {
+        if let Some(name) = rel.strip_prefix("tests/")
+            && name.ends_with(".c")
+            && !name.contains('/')
+            && let Some(contents) = plan.contents(rel)
+        {
+            let source = String::from_utf8_lossy(contents).into_owned();
+            sources.entry(name.to_string()).or_default().push(source);
+        }
+    }
+    sources
+        .iter()
+        .filter_map(|(file_name, versions)| {
+            let stem = file_name.strip_suffix(".c")?;
+            let source = versions
+                .iter()
+                .find(|source| source.contains(&include_line(stem)))?;
+            Some((
+                format!("{}/{}.h", DIR, stem),
+                registration(file_name, &test_functions(source)),
+            ))
+        })
+        .collect()
+}
+
+pub fn run(args: GenTestsArgs, color_enabled: bool) -> ExitCode {
+    let files: Vec<PathBuf> = if args.files.is_empty() {
+        let Some(root) = add::find_project_root() else {
+            print_err(
+                "not inside a c-init project (no manifest, or build file and src/, found)",
+                color_enabled,
+            );
+            return ExitCode::from(1);
+        };
+        let mut files: Vec<PathBuf> = fs::read_dir(root.join("tests"))
+            .map(|entries| {
+                entries
+                    .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+                    .filter(|path| path.extension().is_some_and(|ext| ext == "c"))
+                    .collect()
+            })
+            .unwrap_or_default();
+        files.sort();
+        // Shown relative to where c-init runs, as paths given to it are.
+        let cwd = env::current_dir().unwrap_or_default();
+        files
+            .into_iter()
+            .map(|path| {
+                path.strip_prefix(&cwd)
+                    .map(Path::to_path_buf)
+                    .unwrap_or(path)
+            })
+            .collect()
+    } else {
+        args.files.iter().map(PathBuf::from).collect()
+    };
+
+    let mut problems = Vec::new();
+    for path in &files {
+        if let Err(problem) = register(path, args.check) {
+            problems.push(problem);
+        }
+    }
+    for problem in &problems {
+        print_err(problem, color_enabled);
+    }
+    if problems.is_empty() {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::from(1)
+    }
+}
+
+/// Writes (or with `check`, compares) the registration header of the test
+/// file at `path`. A file with its own `TEST_LIST` is checked for functions
+/// missing from it instead.
+fn register(path: &Path, check: bool) -> Result<(), String> {
+    let display = path.display();
+    let source =
+        fs::read_to_string(path).map_err(|err| format!("failed to read {}: {}", display, err))?;
+    let file_name = path
+        .file_name()
+        .and_then(|name| name.to_str())
+        .ok_or_else(|| format!("{} is not a file name", display))?;
+    let stem = file_name.strip_suffix(".c").unwrap_or(file_name);
+    let functions = test_functions(&source);
+
+    if source.contains(&include_line(stem)) {
+        let header_path = path
+            .parent()
+            .unwrap_or(Path::new(""))
+            .join("generated")
+            .join(format!("{}.h", stem));
+        let header = registration(file_name, &functions);
+        if check {
+            return match fs::read_to_string(&header_path) {
+                Ok(current) if current == header => Ok(()),
+                _ => Err(format!(
+                    "{} is out of date: run `c-init gen-tests`",
+                    header_path.display()
+                )),
+            };
+        }
+        let changed = fs::read_to_string(&header_path).map_or(true, |current| current != header);
+        // Written even when unchanged, so make sees it as newer than the test.
+        write_file(&header_path, &header)
+            .map_err(|err| format!("failed to write {}: {}", header_path.display(), err))?;
+        if changed {
+            info(&format!(
+                "Registered {} test(s) from {}",
+                functions.len(),
+                display
+            ));
+        }
+        return Ok(());
+    }
+
+    // Other frameworks register tests their own way.
+    let Some(registered) = listed(&source) else {
+        return Ok(());
+    };
+    let missing: Vec<&str> = functions
+        .iter()
+        .filter(|function| !registered.contains(function))
+        .map(String::as_str)
+        .collect();
+    if missing.is_empty() {
+        return Ok(());
+    }
+    Err(format!(
+        "{}: {} {} not in TEST_LIST and would never run; list {} there or replace the TEST_LIST with `{}`",
+        display,
+        missing.join(", "),
+        if missing.len() == 1 { "is" } else { "are" },
+        if missing.len() == 1 { "it" } else { "them" },
+        include_line(stem)
+    ))
+}
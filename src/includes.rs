@@ -0,0 +1,118 @@
+use std::fs;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+/// Where package managers put headers that not every compiler searches.
+const PACKAGE_PREFIXES: &[(&str, &str)] = &[
+    ("/opt/homebrew/include", "Homebrew"),
+    ("/usr/local/include", "Homebrew on Intel, make install"),
+    ("/opt/local/include", "MacPorts"),
+];
+
+/// A header directory added to the flags, and how it was found.
+pub struct IncludeDir {
+    pub path: String,
+    pub origin: String,
+}
+
+/// Same directory, whatever symlinks lead to it.
+fn same_dir(a: &str, b: &str) -> bool {
+    match (fs::canonicalize(a), fs::canonicalize(b)) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => a == b,
+    }
+}
+
+/// `cc`'s `#include <...>` search list, as printed by `cc -E -v -x c /dev/null`.
+pub fn search_paths(cc: &str) -> Result<Vec<String>, String> {
+    let mut words = cc.split_whitespace();
+    let program = words.next().ok_or("no compiler command")?;
+    let output = Command::new(program)
+        .args(words)
+        .args(["-E", "-v", "-x", "c", "/dev/null"])
+        .stdin(Stdio::null())
+        .output()
+        .map_err(|err| format!("failed to run {}: {}", program, err))?;
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let paths: Vec<String> = stderr
+        .lines()
+        .skip_while(|line| !line.starts_with("#include <...> search starts here:"))
+        .skip(1)
+        .take_while(|line| !line.starts_with("End of search list."))
+        .map(|line| {
+            line.trim()
+                .trim_end_matches(" (framework directory)")
+                .to_string()
+        })
+        .collect();
+    if paths.is_empty() {
+        return Err(format!("{} printed no include search list", cc));
+    }
+    Ok(paths)
+}
+
+/// `-I` directories pkg-config reports for `package`. Asks for the libraries
+/// too, so a package that can't be linked fails here rather than in `make`.
+pub fn pkg_config(package: &str) -> Result<Vec<String>, String> {
+    let output = Command::new("pkg-config")
+        .args(["--cflags", "--libs", package])
+        .stdin(Stdio::null())
+        .output()
+        .map_err(|err| format!("failed to run pkg-config for '{}': {}", package, err))?;
+    if !output.status.success() {
+        return Err(format!(
+            "pkg-config does not know package '{}': install its development package \
+             (e.g. zlib1g-dev on Debian, zlib on Homebrew) or check the name with \
+             `pkg-config --list-all`",
+            package
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .split_whitespace()
+        .filter_map(|flag| flag.strip_prefix("-I"))
+        .map(str::to_string)
+        .collect())
+}
+
+/// Header directories to add to the flags: package manager prefixes the
+/// compiler does not search (only known when `searched` could be asked
+/// for), then those of every pkg-config package in `packages`. Directories
+/// that don't exist here, or that are already searched, are left out.
+pub fn discover(
+    searched: Option<&[String]>,
+    packages: &[String],
+) -> Result<Vec<IncludeDir>, String> {
+    let mut found: Vec<IncludeDir> = Vec::new();
+    let add = |path: &str, origin: String, found: &mut Vec<IncludeDir>| {
+        let known = searched
+            .unwrap_or_default()
+            .iter()
+            .any(|dir| same_dir(dir, path))
+            || found.iter().any(|dir| same_dir(&dir.path, path));
+        if Path::new(path).is_dir() && !known {
+            found.push(IncludeDir {
+                path: path.to_string(),
+                origin,
+            });
+        }
+    };
+    if searched.is_some() {
+        for (path, manager) in PACKAGE_PREFIXES {
+            add(
+                path,
+                format!("{}; not searched by the compiler", manager),
+                &mut found,
+            );
+        }
+    }
+    for package in packages {
+        for path in pkg_config(package)? {
+            add(
+                &path,
+                format!("pkg-config --cflags {}", package),
+                &mut found,
+            );
+        }
+    }
+    Ok(found)
+}
@@ -1,6 +1,21 @@
-use clap::{ArgAction, CommandFactory, Parser, Subcommand, ValueEnum};
+mod add;
+mod adopt;
+mod compdb;
+mod config;
+mod doctor;
+mod gen_tests;
+mod includes;
+mod manifest;
+mod plan;
+mod probe;
+mod templates;
+mod upgrade;
+mod vendor;
+
+use clap::{ArgAction, Args, CommandFactory, Parser, Subcommand, ValueEnum};
 use dialoguer::{Select, theme::ColorfulTheme};
-use indoc::{formatdoc, indoc};
+use indoc::indoc;
+use minijinja::context;
 use std::collections::VecDeque;
 use std::env;
 use std::fs;
@@ -10,8 +25,6 @@ use std::process::{Command, ExitCode};
 
 const FLAGS_LOOSE_BASE: &str = indoc!(
     r#"
-    -std=c2x
-    -Iinclude
     -Wall
     -Wextra
     "#
@@ -40,12 +53,6 @@ const FLAGS_STRICTEST_COMMON: &str = indoc!(
     -Wdouble-promotion
     "#
 );
-const FLAGS_CLANG_SYSTEM_INCLUDES: &str = indoc!(
-    r#"
-    -isystem/opt/homebrew/include
-    -isystem/usr/local/include
-    "#
-);
 const FLAGS_GCC_STRICT_EXTRA: &str = indoc!(
     r#"
     -Wlogical-op
@@ -63,19 +70,151 @@ const FLAGS_GCC_STRICTEST_EXTRA: &str = indoc!(
     "#
 );
 const FLAGS_CLANG_STRICTEST_EXTRA: &str = "-Wstrict-overflow=5";
+// tcc knows only a handful of warnings, and nothing beyond these for strictest
+const FLAGS_TCC_LOOSE_BASE: &str = "-Wall";
+const FLAGS_TCC_STRICT_EXTRA: &str = indoc!(
+    r#"
+    -Werror
+    -Wwrite-strings
+    -Wunsupported
+    "#
+);
+// Tests are compiled from within ./tests (see `Layout::test_includes`);
+// isystem ./test-deps avoids generating linting warnings for testing library code
 const FLAGS_TEST_INCLUDE: &str = indoc!(
     r#"
-    -I../include
     -I.
     -isystem
     ./test-deps
     "#
 );
 
-#[derive(Clone, Copy, Debug, ValueEnum)]
+/// Compiler family: picks the warning flags and the default command.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
 enum Compiler {
     Clang,
     Gcc,
+    Tcc,
+    Zig,
+    Icx,
+}
+
+impl Compiler {
+    fn as_str(self) -> &'static str {
+        match self {
+            Compiler::Clang => "clang",
+            Compiler::Gcc => "gcc",
+            Compiler::Tcc => "tcc",
+            Compiler::Zig => "zig",
+            Compiler::Icx => "icx",
+        }
+    }
+
+    /// Command for the family, preferring a versioned Homebrew GCC on macOS
+    /// (where `gcc` is clang).
+    fn command(self) -> String {
+        match self {
+            Compiler::Gcc if cfg!(target_os = "macos") => ["gcc-15", "gcc-14", "gcc-13"]
+                .into_iter()
+                .find(|candidate| find_executable(candidate).is_some())
+                .unwrap_or("gcc")
+                .to_string(),
+            Compiler::Zig => "zig cc".to_string(),
+            other => other.as_str().to_string(),
+        }
+    }
+
+    /// Family of an arbitrary compiler command, from what it says about
+    /// itself or, when it can't be run, from its name.
+    fn detect(command: &str) -> Option<Compiler> {
+        let mut words = command.split_whitespace();
+        let program = words.next()?;
+        let rest: Vec<&str> = words.collect();
+        // tcc has no --version; it reports itself with -v
+        let banner = ["--version", "-v"]
+            .into_iter()
+            .filter_map(|flag| {
+                let output = Command::new(program)
+                    .args(&rest)
+                    .arg(flag)
+                    .stdin(std::process::Stdio::null())
+                    .output()
+                    .ok()?;
+                let mut text = String::from_utf8_lossy(&output.stdout).into_owned();
+                text.push_str(&String::from_utf8_lossy(&output.stderr));
+                Some(text.to_ascii_lowercase())
+            })
+            .find(|text| !text.trim().is_empty());
+        let name = Path::new(program)
+            .file_name()
+            .map(|name| name.to_string_lossy().to_ascii_lowercase())
+            .unwrap_or_default();
+        let family = |text: &str| {
+            if text.contains("intel") || text.contains("icx") {
+                Some(Compiler::Icx)
+            } else if text.contains("zig") {
+                Some(Compiler::Zig)
+            } else if text.contains("clang") {
+                Some(Compiler::Clang)
+            } else if text.contains("tcc") || text.contains("tiny c") {
+                Some(Compiler::Tcc)
+            } else if text.contains("gcc") || text.contains("free software foundation") {
+                Some(Compiler::Gcc)
+            } else {
+                None
+            }
+        };
+        banner.as_deref().and_then(family).or_else(|| family(&name))
+    }
+}
+
+/// What `--cc` names: a compiler c-init knows, or any other command (`cc`,
+/// `/opt/llvm-18/bin/clang`) whose family is detected.
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum CcChoice {
+    Known(Compiler),
+    Command(String),
+}
+
+impl CcChoice {
+    fn parse(value: &str) -> Result<CcChoice, String> {
+        let value = value.trim();
+        if value.is_empty() {
+            return Err("expected a compiler name or command".to_string());
+        }
+        Ok(match Compiler::from_str(value, true) {
+            Ok(compiler) => CcChoice::Known(compiler),
+            Err(_) => CcChoice::Command(value.to_string()),
+        })
+    }
+
+    fn command(&self) -> String {
+        match self {
+            CcChoice::Known(compiler) => compiler.command(),
+            CcChoice::Command(command) => command.clone(),
+        }
+    }
+
+    /// Family and explicit command. Commands nothing can be told about get
+    /// gcc's flags, which flag probing then trims.
+    fn resolve(self, color_enabled: bool) -> (Compiler, Option<String>) {
+        match self {
+            CcChoice::Known(compiler) => (compiler, None),
+            CcChoice::Command(command) => {
+                let family = Compiler::detect(&command).unwrap_or_else(|| {
+                    warn(
+                        &format!(
+                            "could not tell which compiler {} is; using gcc's flags",
+                            command
+                        ),
+                        color_enabled,
+                    );
+                    Compiler::Gcc
+                });
+                (family, Some(command))
+            }
+        }
+    }
 }
 
 #[derive(Clone, Copy, Debug, ValueEnum)]
@@ -85,6 +224,129 @@ enum Strictness {
     Strictest,
 }
 
+impl Strictness {
+    fn as_str(self) -> &'static str {
+        match self {
+            Strictness::Loose => "loose",
+            Strictness::Strict => "strict",
+            Strictness::Strictest => "strictest",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum CStd {
+    C89,
+    C99,
+    C11,
+    C17,
+    C23,
+    Gnu89,
+    Gnu99,
+    Gnu11,
+    Gnu17,
+    Gnu23,
+}
+
+impl CStd {
+    fn as_str(self) -> &'static str {
+        match self {
+            CStd::C89 => "c89",
+            CStd::C99 => "c99",
+            CStd::C11 => "c11",
+            CStd::C17 => "c17",
+            CStd::C23 => "c23",
+            CStd::Gnu89 => "gnu89",
+            CStd::Gnu99 => "gnu99",
+            CStd::Gnu11 => "gnu11",
+            CStd::Gnu17 => "gnu17",
+            CStd::Gnu23 => "gnu23",
+        }
+    }
+
+    fn flag(self) -> &'static str {
+        match self {
+            CStd::C89 => "-std=c89",
+            CStd::C99 => "-std=c99",
+            CStd::C11 => "-std=c11",
+            CStd::C17 => "-std=c17",
+            // c2x is understood by compilers that predate the c23 spelling
+            CStd::C23 => "-std=c2x",
+            CStd::Gnu89 => "-std=gnu89",
+            CStd::Gnu99 => "-std=gnu99",
+            CStd::Gnu11 => "-std=gnu11",
+            CStd::Gnu17 => "-std=gnu17",
+            CStd::Gnu23 => "-std=gnu2x",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum TestFramework {
+    Acutest,
+}
+
+impl TestFramework {
+    fn as_str(self) -> &'static str {
+        match self {
+            TestFramework::Acutest => "acutest",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum ProjectKind {
+    Bin,
+    Lib,
+}
+
+impl ProjectKind {
+    fn as_str(self) -> &'static str {
+        match self {
+            ProjectKind::Bin => "bin",
+            ProjectKind::Lib => "lib",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum BuildSystem {
+    Make,
+    Cmake,
+    Meson,
+}
+
+impl BuildSystem {
+    fn as_str(self) -> &'static str {
+        match self {
+            BuildSystem::Make => "make",
+            BuildSystem::Cmake => "cmake",
+            BuildSystem::Meson => "meson",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum DryRun {
+    Tree,
+    Diff,
+}
+
+/// What `--force` does with an existing file that would change.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum OnConflict {
+    /// Keep the existing file
+    Skip,
+    /// Replace it
+    Overwrite,
+    /// Save it as <file>.orig, then replace it
+    Backup,
+    /// Ask for each file
+    Prompt,
+    /// Keep both versions between conflict markers
+    Merge,
+}
+
 #[derive(Clone, Copy, Debug, ValueEnum)]
 enum ColorWhen {
     Auto,
@@ -92,6 +354,107 @@ enum ColorWhen {
     Never,
 }
 
+// Options shared by a new project and `c-init adopt` (a plain comment: clap
+// would show a doc comment as the about text of `c-init --help`).
+#[derive(Debug, Args)]
+struct BuildOptions {
+    /// Compiler: clang (default) | gcc | tcc | zig | icx, or a command such as cc or /opt/llvm-18/bin/clang
+    #[arg(long, value_name = "COMPILER", value_parser = CcChoice::parse)]
+    cc: Option<CcChoice>,
+
+    /// strictness: loose | strict | strictest
+    #[arg(short = 's', long, value_enum)]
+    strictness: Option<Strictness>,
+
+    /// linter strictness: loose | strict | strictest
+    #[arg(long, value_enum)]
+    linter_strictness: Option<Strictness>,
+
+    /// C language standard: c89 | c99 | c11 | c17 | c23 (default) or their gnu variants
+    #[arg(long, value_enum)]
+    std: Option<CStd>,
+
+    /// Cross-compile for a target triple, e.g. aarch64-linux-gnu or x86_64-linux-musl
+    #[arg(long, value_name = "TRIPLE")]
+    target: Option<String>,
+
+    /// Sysroot holding the target's headers and libraries
+    #[arg(long, value_name = "DIR", requires = "target")]
+    sysroot: Option<String>,
+
+    /// Link a system library found with pkg-config, e.g. zlib or sdl2 (repeatable)
+    #[arg(long = "dep", value_name = "PKG")]
+    deps: Vec<String>,
+
+    /// Unit test framework: acutest (default)
+    #[arg(long, value_enum)]
+    test_framework: Option<TestFramework>,
+
+    /// Minimum line coverage (percent) for `make coverage` to pass
+    #[arg(long, value_name = "PERCENT", value_parser = clap::value_parser!(u8).range(0..=100))]
+    coverage_min: Option<u8>,
+
+    /// Keep every warning flag instead of dropping those the compiler rejects
+    #[arg(long, action = ArgAction::SetTrue)]
+    no_probe: bool,
+
+    /// Print what would be generated without writing: tree | diff (against existing files)
+    #[arg(long, value_enum, num_args = 0..=1, require_equals = true, default_missing_value = "tree")]
+    dry_run: Option<DryRun>,
+}
+
+impl BuildOptions {
+    /// Settings for the project `name` of `kind`: each option, else its default
+    /// from the environment or config file, else c-init's own. The rest is
+    /// for a bare Make project, which callers override as needed.
+    fn settings(
+        self,
+        name: String,
+        kind: ProjectKind,
+        defaults: &config::Defaults,
+        color_enabled: bool,
+    ) -> Settings {
+        let (cc, cc_path) = self
+            .cc
+            .or_else(|| defaults.cc.clone())
+            .unwrap_or(CcChoice::Known(Compiler::Clang))
+            .resolve(color_enabled);
+        let strictness = self
+            .strictness
+            .or(defaults.strictness)
+            .unwrap_or(Strictness::Strict);
+        Settings {
+            name,
+            kind,
+            build_system: BuildSystem::Make,
+            cc,
+            cc_path,
+            target: self.target,
+            sysroot: self.sysroot,
+            deps: self.deps,
+            strictness,
+            linter_strictness: self
+                .linter_strictness
+                .or(defaults.linter_strictness)
+                .unwrap_or(strictness),
+            std: self.std.or(defaults.std).unwrap_or(CStd::C23),
+            test_framework: self
+                .test_framework
+                .or(defaults.test_framework)
+                .unwrap_or(TestFramework::Acutest),
+            coverage_min: self.coverage_min.unwrap_or(0),
+            author: defaults.author.clone(),
+            license: defaults.license.clone(),
+            tests: true,
+            hello: false,
+            git: false,
+            readme: false,
+            probe: !self.no_probe,
+            layout: Layout::default(),
+        }
+    }
+}
+
 #[derive(Debug, Parser)]
 #[command(name = "c-init", version, disable_help_subcommand = true)]
 struct Cli {
@@ -103,39 +466,66 @@ struct Cli {
     #[arg(long)]
     name: Option<String>,
 
-    /// Choose compiler
-    #[arg(long, value_enum)]
-    cc: Option<Compiler>,
+    #[command(flatten)]
+    options: BuildOptions,
 
-    /// strictness: loose | strict | strictest
-    #[arg(short = 's', long, value_enum)]
-    strictness: Option<Strictness>,
+    /// Scaffold a static/shared library
+    #[arg(long, action = ArgAction::SetTrue, conflicts_with = "bin")]
+    lib: bool,
 
-    /// linter strictness: loose | strict | strictest
+    /// Scaffold an executable (default)
+    #[arg(long, action = ArgAction::SetTrue)]
+    bin: bool,
+
+    /// Build system: make | cmake | meson
     #[arg(long, value_enum)]
-    linter_strictness: Option<Strictness>,
+    build_system: Option<BuildSystem>,
 
     /// Color: auto | always | never
-    #[arg(long, value_enum, default_value_t = ColorWhen::Auto)]
+    #[arg(long, value_enum, global = true, default_value_t = ColorWhen::Auto)]
     color: ColorWhen,
 
     /// Allow non-empty directory
     #[arg(short = 'f', long, action = ArgAction::SetTrue)]
     force: bool,
 
+    /// Existing files that would change: skip | overwrite (default) | backup | prompt | merge; implies --force
+    #[arg(long, value_enum, value_name = "POLICY")]
+    on_conflict: Option<OnConflict>,
+
+    /// License named in the README
+    #[arg(long)]
+    license: Option<String>,
+
+    /// Author named in the README
+    #[arg(long)]
+    author: Option<String>,
+
+    /// Template directory (or name under <config dir>/templates) overriding the embedded files; repeatable
+    #[arg(long = "template", value_name = "DIR")]
+    templates: Vec<String>,
+
+    /// Run git init and write .gitignore, even if the config says not to
+    #[arg(long, action = ArgAction::SetTrue, conflicts_with = "no_git")]
+    git: bool,
+
     /// Skip git init and .gitignore
     #[arg(long, action = ArgAction::SetTrue)]
     no_git: bool,
 
+    /// Make the initial git commit, even if the config says not to
+    #[arg(long, action = ArgAction::SetTrue, conflicts_with = "no_commit")]
+    commit: bool,
+
     /// Skip initial git commit
     #[arg(long, action = ArgAction::SetTrue)]
     no_commit: bool,
 
-    /// Skip generating src/main.c
+    /// Skip generating src/main.c (or the library's starter header and source)
     #[arg(long, action = ArgAction::SetTrue)]
     no_hello: bool,
 
-    /// Skip generating tests and vendoring acutest
+    /// Skip generating tests and vendoring the test framework
     #[arg(long, action = ArgAction::SetTrue)]
     no_tests: bool,
 
@@ -151,6 +541,29 @@ struct Cli {
 enum Commands {
     /// Show help
     Help,
+    /// Add files to an existing project
+    Add {
+        #[command(subcommand)]
+        command: add::AddCommand,
+    },
+    /// Generate the build files, flags and tests around an existing C codebase
+    Adopt(adopt::AdoptArgs),
+    /// Re-apply this version's templates to a generated project, keeping local edits
+    Upgrade(upgrade::UpgradeArgs),
+    /// Check that the compiler and tools the project uses are installed and work
+    Doctor(doctor::DoctorArgs),
+    /// Copy single-header or small source libraries into the project, pinned in c-init.lock
+    Vendor {
+        #[command(subcommand)]
+        command: vendor::VendorCommand,
+    },
+    /// Write the TEST_LIST of acutest tests that include "generated/<test>.h"
+    GenTests(gen_tests::GenTestsArgs),
+    /// Manage defaults in $XDG_CONFIG_HOME/c-init/config.toml
+    Config {
+        #[command(subcommand)]
+        command: config::ConfigCommand,
+    },
 }
 
 struct InputProvider {
@@ -234,10 +647,10 @@ fn select_menu(
     let mut selected = default_idx;
     if !input.tty {
         let line = input.read_line("")?;
-        if let Ok(idx) = line.trim().parse::<usize>() {
-            if idx < options.len() {
-                selected = idx;
-            }
+        if let Ok(idx) = line.trim().parse::<usize>()
+            && idx < options.len()
+        {
+            selected = idx;
         }
         println!(
             "{}: {} (non-interactive)",
@@ -253,7 +666,7 @@ fn select_menu(
         .items(options)
         .default(default_idx)
         .interact()
-        .map_err(|err| io::Error::new(io::ErrorKind::Other, err))?;
+        .map_err(io::Error::other)?;
 
     selected = selection;
     Ok(selected)
@@ -268,10 +681,10 @@ fn find_executable(name: &str) -> Option<PathBuf> {
 }
 
 fn write_file(path: &Path, contents: &str) -> io::Result<()> {
-    if let Some(parent) = path.parent() {
-        if !parent.as_os_str().is_empty() {
-            fs::create_dir_all(parent)?;
-        }
+    if let Some(parent) = path.parent()
+        && !parent.as_os_str().is_empty()
+    {
+        fs::create_dir_all(parent)?;
     }
     fs::write(path, contents)
 }
@@ -285,48 +698,681 @@ fn flags_concat(parts: &[&str]) -> String {
         .join("\n")
 }
 
-fn fetch_acutest(dest: &Path) -> io::Result<()> {
-    const ACUTEST: &[u8] = include_bytes!("../assets/acutest.h");
-    fs::write(dest, ACUTEST)
+/// Loose, strict and strictest flag sets for a compiler, one flag per line.
+fn flag_sets(cc_choice: Compiler) -> (String, String, String) {
+    match cc_choice {
+        // zig cc and icx are clang underneath
+        Compiler::Clang | Compiler::Zig | Compiler::Icx => {
+            let flags_loose = flags_concat(&[FLAGS_LOOSE_BASE]);
+            let flags_strict = flags_concat(&[&flags_loose, FLAGS_STRICT_COMMON]);
+            let flags_strictest = flags_concat(&[
+                &flags_strict,
+                FLAGS_STRICTEST_COMMON,
+                FLAGS_CLANG_STRICTEST_EXTRA,
+            ]);
+            (flags_loose, flags_strict, flags_strictest)
+        }
+        Compiler::Gcc => {
+            let flags_loose = flags_concat(&[FLAGS_LOOSE_BASE]);
+            let flags_strict =
+                flags_concat(&[&flags_loose, FLAGS_STRICT_COMMON, FLAGS_GCC_STRICT_EXTRA]);
+            let flags_strictest = flags_concat(&[
+                &flags_strict,
+                FLAGS_STRICTEST_COMMON,
+                FLAGS_GCC_STRICTEST_EXTRA,
+            ]);
+            (flags_loose, flags_strict, flags_strictest)
+        }
+        Compiler::Tcc => {
+            let flags_loose = flags_concat(&[FLAGS_TCC_LOOSE_BASE]);
+            let flags_strict = flags_concat(&[&flags_loose, FLAGS_TCC_STRICT_EXTRA]);
+            (flags_loose, flags_strict.clone(), flags_strict)
+        }
+    }
 }
 
-fn main() -> ExitCode {
-    let cli = Cli::parse();
-    if matches!(cli.command, Some(Commands::Help)) {
-        let mut cmd = Cli::command();
-        let _ = cmd.print_help();
-        println!();
-        return ExitCode::SUCCESS;
+/// Name used for generated file names and the Makefile `NAME`.
+fn project_file_name(name: &str) -> String {
+    name.to_ascii_lowercase().replace(' ', "_")
+}
+
+/// Turns a project name into something usable as a C identifier prefix.
+fn c_ident(name: &str) -> String {
+    let mut ident: String = name
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_lowercase()
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    if ident.is_empty() || ident.starts_with(|c: char| c.is_ascii_digit()) {
+        ident.insert(0, '_');
+    }
+    ident
+}
+
+fn fetch_acutest() -> &'static [u8] {
+    include_bytes!("../assets/acutest.h")
+}
+
+/// Where an existing project keeps its code, for the build files and flags.
+struct Layout {
+    src_dirs: Vec<String>,
+    inc_dirs: Vec<String>,
+    /// Source files (without `.c`) defining `main()`, left out of test links.
+    entry_points: Vec<String>,
+}
+
+impl Default for Layout {
+    fn default() -> Self {
+        Self {
+            src_dirs: vec!["src".to_string()],
+            inc_dirs: vec!["include".to_string()],
+            entry_points: vec!["main".to_string()],
+        }
+    }
+}
+
+impl Layout {
+    /// `-I` flags for sources compiled from the project root.
+    fn app_includes(&self) -> String {
+        self.inc_dirs
+            .iter()
+            .map(|dir| format!("-I{}", dir))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// `-I` flags for tests, which are compiled from within ./tests.
+    fn test_includes(&self) -> String {
+        let includes = self
+            .inc_dirs
+            .iter()
+            .map(|dir| match dir.as_str() {
+                "." => "-I..".to_string(),
+                dir => format!("-I../{}", dir),
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+        flags_concat(&[&includes, FLAGS_TEST_INCLUDE])
+    }
+
+    /// clang-tidy `HeaderFilterRegex` matching the header directories.
+    fn header_filter(&self) -> String {
+        if self.inc_dirs.iter().any(|dir| dir == ".") {
+            return ".*".to_string();
+        }
+        let dirs: Vec<String> = self
+            .inc_dirs
+            .iter()
+            .map(|dir| dir.replace('.', "\\."))
+            .collect();
+        match dirs.as_slice() {
+            [dir] => format!("{}/.*", dir),
+            _ => format!("({})/.*", dirs.join("|")),
+        }
+    }
+}
+
+/// Everything that decides what gets generated, once CLI flags, config and
+/// the wizard have been resolved.
+struct Settings {
+    name: String,
+    kind: ProjectKind,
+    build_system: BuildSystem,
+    cc: Compiler,
+    /// Command given with `--cc` instead of a known compiler name.
+    cc_path: Option<String>,
+    /// Target triple when cross-compiling.
+    target: Option<String>,
+    sysroot: Option<String>,
+    /// pkg-config packages the project links against.
+    deps: Vec<String>,
+    strictness: Strictness,
+    linter_strictness: Strictness,
+    std: CStd,
+    test_framework: TestFramework,
+    /// Line coverage `make coverage` requires, in percent; 0 for none.
+    coverage_min: u8,
+    author: Option<String>,
+    license: Option<String>,
+    tests: bool,
+    hello: bool,
+    git: bool,
+    readme: bool,
+    /// Test the flags against the compiler and leave out the ones it rejects.
+    probe: bool,
+    layout: Layout,
+}
+
+impl Settings {
+    /// Compiler command, including what it needs to cross-compile. An explicit
+    /// gcc path is taken to be a cross compiler already.
+    fn cc_command(&self) -> String {
+        let mut command = self.cc_path.clone().unwrap_or_else(|| self.cc.command());
+        if let Some(triple) = &self.target {
+            match self.cc {
+                Compiler::Gcc if self.cc_path.is_none() => command = format!("{}-gcc", triple),
+                Compiler::Clang | Compiler::Icx => {
+                    command.push_str(&format!(" --target={}", triple))
+                }
+                Compiler::Zig => command.push_str(&format!(" -target {}", triple)),
+                Compiler::Gcc | Compiler::Tcc => {}
+            }
+        }
+        if let Some(sysroot) = &self.sysroot {
+            command.push_str(&format!(" --sysroot={}", sysroot));
+        }
+        command
+    }
+
+    /// `tool` from the compiler's own toolchain: `gcc-14` pairs with `gcov-14`,
+    /// `aarch64-linux-gnu-gcc` with `aarch64-linux-gnu-gcov` and
+    /// `/opt/llvm/bin/clang-18` with `/opt/llvm/bin/llvm-cov-18`.
+    fn toolchain_tool(&self, tool: &str) -> String {
+        let command = self.cc_command();
+        let program = command.split_whitespace().next().unwrap_or_default();
+        let (dir, base) = program.rsplit_once('/').unwrap_or(("", program));
+        let renamed = match self.cc {
+            Compiler::Gcc => base
+                .rfind("gcc")
+                .map(|at| format!("{}{}{}", &base[..at], tool, &base[at + "gcc".len()..])),
+            Compiler::Clang | Compiler::Icx => base
+                .strip_prefix(self.cc.as_str())
+                .map(|suffix| format!("{}{}", tool, suffix)),
+            Compiler::Tcc | Compiler::Zig => None,
+        };
+        match renamed {
+            Some(name) if !dir.is_empty() => format!("{}/{}", dir, name),
+            Some(name) => name,
+            None => tool.to_string(),
+        }
+    }
+
+    /// Checks options that only work together.
+    fn validate(&self) -> Result<(), String> {
+        let Some(triple) = &self.target else {
+            return Ok(());
+        };
+        if triple.split('-').filter(|part| !part.is_empty()).count() < 2 {
+            return Err(format!(
+                "invalid target '{}' (expected a triple such as aarch64-linux-gnu)",
+                triple
+            ));
+        }
+        if self.cc == Compiler::Tcc {
+            return Err(
+                "tcc cannot cross-compile; use --cc gcc, clang or zig with --target".into(),
+            );
+        }
+        if self.build_system != BuildSystem::Make {
+            return Err(format!(
+                "--target is only supported with the make build system, not {}",
+                self.build_system.as_str()
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// `uname -m`-style architecture of a target triple.
+fn target_arch(triple: &str) -> &str {
+    match triple.split('-').next().unwrap_or(triple) {
+        "arm64" => "aarch64",
+        arch => arch,
+    }
+}
+
+/// User-mode qemu command that runs binaries built for `triple`, loading
+/// shared libraries from the sysroot (Debian's cross toolchains use
+/// `/usr/<triple>`).
+fn qemu_runner(triple: &str, sysroot: Option<&str>) -> String {
+    let arch = target_arch(triple);
+    let qemu = if arch.starts_with("arm") {
+        "arm"
+    } else if matches!(arch, "i386" | "i486" | "i586" | "i686") {
+        "i386"
+    } else {
+        arch
+    };
+    let prefix = sysroot.map_or_else(|| format!("/usr/{}", triple), str::to_string);
+    format!("qemu-{} -L {}", qemu, prefix)
+}
+
+/// Renders every file for `settings` into a plan rooted at `root`.
+fn build_plan(
+    settings: &Settings,
+    templates: &templates::Templates,
+    root: PathBuf,
+    color_enabled: bool,
+) -> Result<plan::Plan, String> {
+    let vendored = vendor::load(&root)?;
+    build_plan_with(settings, templates, root, vendored, color_enabled)
+}
+
+/// [`build_plan`] with `vendored` in place of the dependencies in the
+/// lockfile, for `c-init vendor` to wire in a lockfile it has not written yet.
+fn build_plan_with(
+    settings: &Settings,
+    templates: &templates::Templates,
+    root: PathBuf,
+    vendored: Vec<vendor::Vendored>,
+    color_enabled: bool,
+) -> Result<plan::Plan, String> {
+    settings.validate()?;
+    let layout = &settings.layout;
+    let mut plan = plan::Plan::new(root);
+    for dir in layout.src_dirs.iter().chain(&layout.inc_dirs) {
+        if dir != "." {
+            plan.dir(dir);
+        }
+    }
+    plan.dir("target");
+
+    let actual_cc = settings.cc_command();
+    // A cross compiler has its own headers: the host's package prefixes don't apply.
+    let searched = if settings.probe && settings.target.is_none() {
+        includes::search_paths(&actual_cc).ok()
+    } else {
+        None
+    };
+    let system_includes = includes::discover(searched.as_deref(), &settings.deps)?;
+    let include_flags = system_includes
+        .iter()
+        .map(|dir| format!("-isystem{}", dir.path))
+        .collect::<Vec<_>>()
+        .join("\n");
+    let (app_vendored, test_vendored): (Vec<_>, Vec<_>) =
+        vendored.iter().partition(|dep| !dep.test);
+    let (flags_loose, flags_strict, flags_strictest) = flag_sets(settings.cc);
+    let unsupported = if settings.probe {
+        let mut candidates = vec![settings.std.flag()];
+        candidates.extend(flags_strictest.lines());
+        match probe::unsupported(&actual_cc, &candidates) {
+            // Unlike a missing warning, a different standard changes what compiles.
+            Ok(unsupported) if unsupported.iter().any(|flag| flag == settings.std.flag()) => {
+                return Err(format!(
+                    "{} does not support {} (--std {}); choose another standard",
+                    actual_cc,
+                    settings.std.flag(),
+                    settings.std.as_str()
+                ));
+            }
+            Ok(unsupported) => unsupported,
+            Err(err) => {
+                warn(
+                    &format!(
+                        "could not probe flags ({}); keeping all of them (pass --no-probe to skip probing)",
+                        err
+                    ),
+                    color_enabled,
+                );
+                Vec::new()
+            }
+        }
+    } else {
+        Vec::new()
+    };
+    if !unsupported.is_empty() {
+        warn(
+            &format!(
+                "{} does not support {}; left out of the generated flags",
+                actual_cc,
+                unsupported.join(" ")
+            ),
+            color_enabled,
+        );
+    }
+    let supported = |flags: &str| -> String {
+        flags
+            .lines()
+            .filter(|flag| !unsupported.iter().any(|rejected| rejected == flag.trim()))
+            .collect::<Vec<_>>()
+            .join("\n")
+    };
+    let (flags_loose, flags_strict, flags_strictest) = (
+        supported(&flags_loose),
+        supported(&flags_strict),
+        supported(&flags_strictest),
+    );
+    let flag_list = |flags: &str| -> Vec<String> {
+        flags
+            .lines()
+            .map(str::trim)
+            .filter(|flag| !flag.is_empty())
+            .map(str::to_string)
+            .collect()
+    };
+    let ctx = context! {
+        project_name => &settings.name,
+        name => project_file_name(&settings.name),
+        ident => c_ident(&settings.name),
+        cc => &actual_cc,
+        compiler => settings.cc.as_str(),
+        kind => settings.kind.as_str(),
+        build_system => settings.build_system.as_str(),
+        strictness => settings.strictness.as_str(),
+        linter_strictness => settings.linter_strictness.as_str(),
+        std => settings.std.as_str(),
+        std_flag => settings.std.flag(),
+        test_framework => settings.test_framework.as_str(),
+        coverage => context! {
+            min => settings.coverage_min,
+            gcov => settings.toolchain_tool("gcov"),
+            profdata => settings.toolchain_tool("llvm-profdata"),
+            cov => settings.toolchain_tool("llvm-cov"),
+        },
+        author => &settings.author,
+        license => &settings.license,
+        target => settings.target.as_ref().map(|triple| context! {
+            triple => triple,
+            arch => target_arch(triple),
+            runner => qemu_runner(triple, settings.sysroot.as_deref()),
+        }),
+        deps => &settings.deps,
+        // libraries copied in with `c-init vendor`, paths relative to the root
+        vendor => context! {
+            includes => app_vendored.iter().map(|dep| dep.include_dir()).collect::<Vec<_>>(),
+            sources => app_vendored.iter().flat_map(|dep| dep.sources()).collect::<Vec<_>>(),
+            test_includes => test_vendored.iter().map(|dep| dep.include_dir()).collect::<Vec<_>>(),
+            test_sources => test_vendored.iter().flat_map(|dep| dep.sources()).collect::<Vec<_>>(),
+        },
+        // header directories found on this machine, and how
+        system_includes => system_includes
+            .iter()
+            .map(|dir| context! { path => &dir.path, origin => &dir.origin })
+            .collect::<Vec<_>>(),
+        features => context! {
+            tests => settings.tests,
+            hello => settings.hello,
+            git => settings.git,
+        },
+        layout => context! {
+            src_dirs => &layout.src_dirs,
+            inc_dirs => &layout.inc_dirs,
+            entry_points => &layout.entry_points,
+            header_filter => layout.header_filter(),
+        },
+        // selected compiler, one list per strictness level
+        flags => context! {
+            loose => flag_list(&flags_loose),
+            strict => flag_list(&flags_strict),
+            strictest => flag_list(&flags_strictest),
+        },
+        // building blocks for build files that pick flags at configure time
+        flag_groups => vec![
+            ("loose_base", flag_list(&supported(FLAGS_LOOSE_BASE))),
+            ("strict_common", flag_list(&supported(FLAGS_STRICT_COMMON))),
+            ("gcc_strict_extra", flag_list(&supported(FLAGS_GCC_STRICT_EXTRA))),
+            ("strictest_common", flag_list(&supported(FLAGS_STRICTEST_COMMON))),
+            ("gcc_strictest_extra", flag_list(&supported(FLAGS_GCC_STRICTEST_EXTRA))),
+            ("clang_strictest_extra", flag_list(&supported(FLAGS_CLANG_STRICTEST_EXTRA))),
+            ("tcc_loose_base", flag_list(&supported(FLAGS_TCC_LOOSE_BASE))),
+            ("tcc_strict_extra", flag_list(&supported(FLAGS_TCC_STRICT_EXTRA))),
+        ],
+    };
+    let renderer = templates::Renderer::new(templates, ctx)?;
+
+    let mut rendered: Vec<&str> = Vec::new();
+    if settings.hello {
+        match settings.kind {
+            ProjectKind::Bin => rendered.push("src/main.c"),
+            ProjectKind::Lib => {
+                rendered.push("include/{{ name }}/{{ name }}.h");
+                rendered.push("src/{{ name }}.c");
+            }
+        }
+    }
+    if settings.tests {
+        rendered.push("tests/test_basic.c");
+    }
+    match settings.build_system {
+        BuildSystem::Make => rendered.push("Makefile"),
+        BuildSystem::Cmake => rendered.push("CMakeLists.txt"),
+        BuildSystem::Meson => {
+            rendered.push("meson.build");
+            rendered.push("meson_options.txt");
+        }
+    }
+    rendered.push(".clang-tidy");
+    if settings.readme {
+        rendered.push("README.md");
+    }
+    if settings.git && !plan.root().join(".git").exists() {
+        rendered.push(".gitignore");
+    }
+    for key in rendered {
+        let (rel, contents) = renderer.render(key)?;
+        plan.file(&rel, contents);
     }
 
+    if settings.tests {
+        match settings.test_framework {
+            TestFramework::Acutest => {
+                const ACUTEST_PATH: &str = "tests/test-deps/acutest.h";
+                let header = match renderer.vendored(ACUTEST_PATH) {
+                    Some(source) => fs::read(source).map_err(|err| {
+                        format!("failed to read {}: {}", source.display(), err)
+                    })?,
+                    None => fetch_acutest().to_vec(),
+                };
+                plan.file(ACUTEST_PATH, header);
+            }
+        }
+    }
+
+    for (rel, contents) in renderer.extras()? {
+        plan.file(&rel, contents);
+    }
+    if settings.tests {
+        for (rel, contents) in gen_tests::headers(&plan) {
+            plan.file(&rel, contents);
+        }
+    }
+
+    let selected_flags = match settings.strictness {
+        Strictness::Loose => flags_loose,
+        Strictness::Strict => flags_strict,
+        Strictness::Strictest => flags_strictest,
+    };
+    let app_vendor_flags = app_vendored
+        .iter()
+        .map(|dep| format!("-isystem{}", dep.include_dir()))
+        .collect::<Vec<_>>()
+        .join("\n");
+    let app_flags = flags_concat(&[
+        settings.std.flag(),
+        &layout.app_includes(),
+        &include_flags,
+        &app_vendor_flags,
+        &selected_flags,
+    ]);
+    plan.file("compile_flags.txt", app_flags.as_str());
+
+    // Tests are compiled from within ./tests and see every vendored library.
+    let test_vendor_flags = vendored
+        .iter()
+        .map(|dep| format!("-isystem../{}", dep.include_dir()))
+        .collect::<Vec<_>>()
+        .join("\n");
+    let test_flags = flags_concat(&[
+        settings.std.flag(),
+        &layout.test_includes(),
+        &include_flags,
+        &test_vendor_flags,
+        &selected_flags,
+    ]);
+    if settings.tests {
+        plan.file("tests/compile_flags.txt", test_flags.as_str());
+    }
+
+    if settings.build_system == BuildSystem::Make {
+        let planned: Vec<&str> = plan.paths().collect();
+        let json = compdb::render(
+            plan.root(),
+            &actual_cc,
+            &app_flags,
+            settings.tests.then_some(test_flags.as_str()),
+            &layout.src_dirs,
+            &planned,
+        )
+        .map_err(|err| format!("failed to render compile_commands.json: {}", err))?;
+        plan.file("compile_commands.json", json);
+    }
+
+    manifest::record(&mut plan, settings, templates.requested());
+    Ok(plan)
+}
+
+/// Resolves conflicts with existing files, then either previews the plan
+/// (returning `None`) or writes it (returning what happened to each file).
+fn apply_plan(
+    plan: &mut plan::Plan,
+    on_conflict: OnConflict,
+    dry_run: Option<DryRun>,
+    input: &mut Option<InputProvider>,
+    color_enabled: bool,
+) -> Result<Option<plan::Summary>, String> {
+    // A dry run shows every conflict as an overwrite rather than asking.
+    let conflicts = plan.conflicts();
+    if on_conflict == OnConflict::Prompt
+        && dry_run.is_none()
+        && !conflicts.is_empty()
+        && input.is_none()
+    {
+        *input = Some(
+            InputProvider::new(true).map_err(|err| format!("failed to read input: {}", err))?,
+        );
+    }
+    for rel in conflicts {
+        let resolution = match on_conflict {
+            OnConflict::Skip => plan::Resolution::Skip,
+            OnConflict::Overwrite => plan::Resolution::Overwrite,
+            OnConflict::Backup => plan::Resolution::Backup,
+            OnConflict::Merge => plan::Resolution::Merge,
+            OnConflict::Prompt => {
+                let Some(input) = input.as_mut().filter(|_| dry_run.is_none()) else {
+                    continue;
+                };
+                match select_menu(
+                    input,
+                    &format!("{} exists and differs", rel),
+                    &["skip", "overwrite", "backup", "merge"],
+                    0,
+                    color_enabled,
+                )
+                .map_err(|err| format!("failed to read input: {}", err))?
+                {
+                    1 => plan::Resolution::Overwrite,
+                    2 => plan::Resolution::Backup,
+                    3 => plan::Resolution::Merge,
+                    _ => plan::Resolution::Skip,
+                }
+            }
+        };
+        plan.resolve(&rel, resolution);
+    }
+
+    if let Some(mode) = dry_run {
+        plan.print_tree(color_enabled);
+        if mode == DryRun::Diff {
+            info("");
+            plan.print_diff(color_enabled);
+        }
+        info("");
+        info(&muted(
+            &format!(
+                "Dry run: {} files, {} would overwrite existing files. Nothing was written.",
+                plan.len(),
+                plan.overwrites()
+            ),
+            color_enabled,
+        ));
+        return Ok(None);
+    }
+
+    let summary = plan.summary();
+    plan.write()?;
+    Ok(Some(summary))
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
     let color_enabled = match cli.color {
         ColorWhen::Always => true,
         ColorWhen::Never => false,
         ColorWhen::Auto => atty::is(atty::Stream::Stdout),
     };
 
+    match cli.command {
+        Some(Commands::Help) => {
+            let mut cmd = Cli::command();
+            let _ = cmd.print_help();
+            println!();
+            return ExitCode::SUCCESS;
+        }
+        Some(Commands::Add { command }) => return add::run(command, color_enabled),
+        Some(Commands::Adopt(args)) => return adopt::run(args, color_enabled),
+        Some(Commands::Upgrade(args)) => return upgrade::run(args, color_enabled),
+        Some(Commands::Doctor(args)) => return doctor::run(args, color_enabled),
+        Some(Commands::Vendor { command }) => return vendor::run(command, color_enabled),
+        Some(Commands::GenTests(args)) => return gen_tests::run(args, color_enabled),
+        Some(Commands::Config { command }) => return config::run(command, color_enabled),
+        None => {}
+    }
+
+    // CLI flag > env var > config file > built-in default
+    let defaults = match config::Config::load().and_then(|cfg| config::Defaults::resolve(&cfg)) {
+        Ok(defaults) => defaults,
+        Err(err) => {
+            print_err(&err, color_enabled);
+            return ExitCode::from(1);
+        }
+    };
+    let templates = match templates::Templates::load(&cli.templates) {
+        Ok(templates) => templates,
+        Err(err) => {
+            print_err(&err, color_enabled);
+            return ExitCode::from(1);
+        }
+    };
+
     let mut proj_name = cli.name;
     let mut proj_path = cli.path;
-    let mut cc_choice = cli.cc;
-    let mut strictness = cli.strictness;
-    let mut linter_strictness = cli.linter_strictness;
-    let mut force = cli.force;
-    let mut no_git = cli.no_git;
-    let no_commit = cli.no_commit;
+    let mut options = cli.options;
+    let mut force = cli.force || cli.on_conflict.is_some();
+    let on_conflict = cli.on_conflict.unwrap_or(OnConflict::Overwrite);
+    // A flag either way wins over the environment and config file.
+    let mut no_git = cli.no_git || (!cli.git && defaults.git == Some(false));
+    let no_commit = cli.no_commit || (!cli.commit && defaults.git_commit == Some(false));
+    let license = cli.license.or_else(|| defaults.license.clone());
+    let author = cli.author.or_else(|| defaults.author.clone());
     let no_hello = cli.no_hello;
+    let mut build_system = cli.build_system;
+    let mut kind = if cli.lib {
+        Some(ProjectKind::Lib)
+    } else if cli.bin {
+        Some(ProjectKind::Bin)
+    } else {
+        None
+    };
     let mut no_tests = cli.no_tests;
+    let mut input = None;
 
     if cli.interactive {
         info("--- c-init Interactive Wizard ---");
         info("");
 
-        let mut input = match InputProvider::new(true) {
+        let input = input.insert(match InputProvider::new(true) {
             Ok(input) => input,
             Err(err) => {
                 print_err(&format!("failed to read input: {}", err), color_enabled);
                 return ExitCode::from(1);
             }
-        };
+        });
 
         if proj_name.is_none() && proj_path.is_none() {
             let entry = match input.read_line("Project Name [.]: ") {
@@ -345,7 +1391,7 @@ fn main() -> ExitCode {
         let path_for_check = PathBuf::from(path_for_check);
         if is_dir_nonempty(&path_for_check).unwrap_or(false) && !force {
             let res = match select_menu(
-                &mut input,
+                input,
                 "Folder not empty. Overwrite?",
                 &["No", "Yes"],
                 0,
@@ -365,28 +1411,88 @@ fn main() -> ExitCode {
             }
         }
 
-        if cc_choice.is_none() {
-            let res = match select_menu(&mut input, "Compiler", &["clang", "gcc"], 0, color_enabled)
-            {
+        if kind.is_none() {
+            let res = match select_menu(
+                input,
+                "Project Kind",
+                &["binary", "library"],
+                0,
+                color_enabled,
+            ) {
                 Ok(res) => res,
                 Err(err) => {
                     print_err(&format!("failed to read input: {}", err), color_enabled);
                     return ExitCode::from(1);
                 }
             };
-            cc_choice = Some(if res == 1 {
-                Compiler::Gcc
+            kind = Some(if res == 1 {
+                ProjectKind::Lib
             } else {
-                Compiler::Clang
+                ProjectKind::Bin
+            });
+        }
+
+        if options.cc.is_none() {
+            let choices = [
+                CcChoice::Known(Compiler::Clang),
+                CcChoice::Known(Compiler::Gcc),
+                CcChoice::Known(Compiler::Tcc),
+                CcChoice::Known(Compiler::Zig),
+                CcChoice::Known(Compiler::Icx),
+                CcChoice::Command("cc".to_string()),
+            ];
+            let default_idx = defaults
+                .cc
+                .as_ref()
+                .and_then(|default| choices.iter().position(|choice| choice == default))
+                .unwrap_or(0);
+            let res = match select_menu(
+                input,
+                "Compiler",
+                &["clang", "gcc", "tcc", "zig cc", "icx", "cc (system default)"],
+                default_idx,
+                color_enabled,
+            ) {
+                Ok(res) => res,
+                Err(err) => {
+                    print_err(&format!("failed to read input: {}", err), color_enabled);
+                    return ExitCode::from(1);
+                }
+            };
+            options.cc = Some(choices[res].clone());
+        }
+
+        if build_system.is_none() {
+            let res = match select_menu(
+                input,
+                "Build System",
+                &["make", "cmake", "meson"],
+                0,
+                color_enabled,
+            ) {
+                Ok(res) => res,
+                Err(err) => {
+                    print_err(&format!("failed to read input: {}", err), color_enabled);
+                    return ExitCode::from(1);
+                }
+            };
+            build_system = Some(match res {
+                1 => BuildSystem::Cmake,
+                2 => BuildSystem::Meson,
+                _ => BuildSystem::Make,
             });
         }
 
-        if strictness.is_none() {
+        if options.strictness.is_none() {
             let res = match select_menu(
-                &mut input,
+                input,
                 "Compiler Strictness",
                 &["loose", "strict", "strictest"],
-                1,
+                match defaults.strictness {
+                    Some(Strictness::Loose) => 0,
+                    Some(Strictness::Strictest) => 2,
+                    _ => 1,
+                },
                 color_enabled,
             ) {
                 Ok(res) => res,
@@ -395,19 +1501,24 @@ fn main() -> ExitCode {
                     return ExitCode::from(1);
                 }
             };
-            strictness = Some(match res {
+            options.strictness = Some(match res {
                 0 => Strictness::Loose,
                 1 => Strictness::Strict,
                 _ => Strictness::Strictest,
             });
         }
 
-        if linter_strictness.is_none() {
+        if options.linter_strictness.is_none() {
             let res = match select_menu(
-                &mut input,
+                input,
                 "Linter Strictness",
                 &["(same as strictness)", "loose", "strict", "strictest"],
-                0,
+                match defaults.linter_strictness {
+                    Some(Strictness::Loose) => 1,
+                    Some(Strictness::Strict) => 2,
+                    Some(Strictness::Strictest) => 3,
+                    None => 0,
+                },
                 color_enabled,
             ) {
                 Ok(res) => res,
@@ -416,7 +1527,7 @@ fn main() -> ExitCode {
                     return ExitCode::from(1);
                 }
             };
-            linter_strictness = match res {
+            options.linter_strictness = match res {
                 1 => Some(Strictness::Loose),
                 2 => Some(Strictness::Strict),
                 3 => Some(Strictness::Strictest),
@@ -424,13 +1535,36 @@ fn main() -> ExitCode {
             };
         }
 
-        let provided_no_git = env::args().any(|arg| arg == "--no-git");
-        if !provided_no_git {
+        if options.std.is_none() {
+            let standards = CStd::value_variants();
+            let names: Vec<&str> = standards.iter().map(|std| std.as_str()).collect();
+            let default_std = defaults.std.unwrap_or(CStd::C23);
+            let res = match select_menu(
+                input,
+                "C Standard",
+                &names,
+                standards
+                    .iter()
+                    .position(|std| *std == default_std)
+                    .unwrap_or(0),
+                color_enabled,
+            ) {
+                Ok(res) => res,
+                Err(err) => {
+                    print_err(&format!("failed to read input: {}", err), color_enabled);
+                    return ExitCode::from(1);
+                }
+            };
+            options.std = Some(standards[res]);
+        }
+
+        let provided_git = env::args().any(|arg| arg == "--git" || arg == "--no-git");
+        if !provided_git {
             let res = match select_menu(
-                &mut input,
+                input,
                 "Run git init?",
                 &["No", "Yes"],
-                1,
+                if no_git { 0 } else { 1 },
                 color_enabled,
             ) {
                 Ok(res) => res,
@@ -445,7 +1579,7 @@ fn main() -> ExitCode {
         let provided_no_tests = env::args().any(|arg| arg == "--no-tests");
         if !provided_no_tests {
             let res = match select_menu(
-                &mut input,
+                input,
                 "Generate tests?",
                 &["No", "Yes"],
                 1,
@@ -463,9 +1597,8 @@ fn main() -> ExitCode {
         info("");
     }
 
-    let cc_choice = cc_choice.unwrap_or(Compiler::Clang);
-    let strictness = strictness.unwrap_or(Strictness::Strict);
-    let linter_strictness = linter_strictness.unwrap_or(strictness);
+    let kind = kind.unwrap_or(ProjectKind::Bin);
+    let build_system = build_system.unwrap_or(BuildSystem::Make);
 
     let mut proj_path = proj_path.unwrap_or_else(|| ".".to_string());
     if proj_path.is_empty() {
@@ -473,22 +1606,13 @@ fn main() -> ExitCode {
     }
 
     let path = PathBuf::from(&proj_path);
-    if path != Path::new(".") {
-        if let Err(err) = fs::create_dir_all(&path) {
-            print_err(
-                &format!("failed to create {}: {}", proj_path, err),
-                color_enabled,
-            );
-            return ExitCode::from(1);
-        }
-    }
 
     if proj_name.is_none() {
         if path == Path::new(".") {
-            if let Ok(current) = env::current_dir() {
-                if let Some(name) = current.file_name().and_then(|s| s.to_str()) {
-                    proj_name = Some(name.to_string());
-                }
+            if let Ok(current) = env::current_dir()
+                && let Some(name) = current.file_name().and_then(|s| s.to_str())
+            {
+                proj_name = Some(name.to_string());
             }
         } else if let Some(name) = path.file_name().and_then(|s| s.to_str()) {
             proj_name = Some(name.to_string());
@@ -496,7 +1620,7 @@ fn main() -> ExitCode {
     }
 
     let proj_name = proj_name.unwrap_or_else(|| "project".to_string());
-    let proj_name_lower = proj_name.to_ascii_lowercase().replace(' ', "_");
+    let proj_name_lower = project_file_name(&proj_name);
 
     if is_dir_nonempty(&path).unwrap_or(false) && !force {
         print_err(
@@ -509,250 +1633,158 @@ fn main() -> ExitCode {
         return ExitCode::from(1);
     }
 
-    if let Err(err) = env::set_current_dir(&path) {
-        print_err(
-            &format!("failed to enter {}: {}", proj_path, err),
-            color_enabled,
-        );
-        return ExitCode::from(1);
-    }
-
-    if let Err(err) = fs::create_dir_all("src") {
-        print_err(&format!("failed to create src: {}", err), color_enabled);
-        return ExitCode::from(1);
-    }
-    if let Err(err) = fs::create_dir_all("include") {
-        print_err(&format!("failed to create include: {}", err), color_enabled);
-        return ExitCode::from(1);
-    }
-    if let Err(err) = fs::create_dir_all("target") {
-        print_err(&format!("failed to create target: {}", err), color_enabled);
-        return ExitCode::from(1);
-    }
-
-    let mut actual_cc = match cc_choice {
-        Compiler::Clang => "clang".to_string(),
-        Compiler::Gcc => "gcc".to_string(),
-    };
-
-    if matches!(cc_choice, Compiler::Gcc) && cfg!(target_os = "macos") {
-        for candidate in ["gcc-15", "gcc-14", "gcc-13"] {
-            if find_executable(candidate).is_some() {
-                actual_cc = candidate.to_string();
-                break;
-            }
-        }
-    }
-
-    if !no_hello {
-        let main_c = formatdoc!(
-            r#"
-            #include <stdio.h>
-
-            int main(void) {{
-              printf("Hello from %s!\n", "{proj_name}");
-              return 0;
-            }}
-            "#,
-            proj_name = proj_name
-        );
-        if let Err(err) = write_file(Path::new("src/main.c"), &main_c) {
-            print_err(
-                &format!("failed to write src/main.c: {}", err),
-                color_enabled,
-            );
-            return ExitCode::from(1);
-        }
-    }
-
-    if !no_tests {
-        if let Err(err) = fs::create_dir_all("tests/test-deps") {
-            print_err(
-                &format!("failed to create tests/test-deps: {}", err),
-                color_enabled,
-            );
-            return ExitCode::from(1);
-        }
-        if let Err(err) = fetch_acutest(Path::new("tests/test-deps/acutest.h")) {
-            print_err(&format!("failed to write acutest: {}", err), color_enabled);
-            return ExitCode::from(1);
-        }
-        const TEST_BASIC: &[u8] = include_bytes!("../assets/test_basic.c");
-        if let Err(err) = fs::write(Path::new("tests/test_basic.c"), TEST_BASIC) {
+    let root = match plan::absolute_root(&path) {
+        Ok(root) => root,
+        Err(err) => {
             print_err(
-                &format!("failed to write tests/test_basic.c: {}", err),
+                &format!("failed to resolve {}: {}", proj_path, err),
                 color_enabled,
             );
             return ExitCode::from(1);
         }
-    }
-
-    let makefile_template = include_str!("../assets/Makefile");
-    let phony = if !no_tests {
-        "all run release run-release test sanitize fmt lint clean"
-    } else {
-        "all run release run-release sanitize fmt lint clean"
     };
-    let mut makefile = makefile_template
-        .replace("{CC}", &actual_cc)
-        .replace("{NAME}", &proj_name_lower)
-        .replace("{PHONY}", phony);
-    if no_tests {
-        if let (Some(start), Some(end)) = (
-            makefile.find("# TEST_SECTION_BEGIN"),
-            makefile.find("# TEST_SECTION_END"),
-        ) {
-            let end = end + "# TEST_SECTION_END".len();
-            makefile.replace_range(
-                start..end,
-                "sanitize:\n\t@$(MAKE) SANITIZE=1 MODE=debug all\n",
-            );
-        }
-    } else {
-        makefile = makefile
-            .replace("# TEST_SECTION_BEGIN\n", "")
-            .replace("\n# TEST_SECTION_END", "");
-    }
-    if let Err(err) = write_file(Path::new("Makefile"), &makefile) {
-        print_err(&format!("failed to write Makefile: {}", err), color_enabled);
-        return ExitCode::from(1);
-    }
-
-    let (flags_loose, flags_strict, flags_strictest) = match cc_choice {
-        Compiler::Clang => {
-            let flags_loose = flags_concat(&[FLAGS_LOOSE_BASE, FLAGS_CLANG_SYSTEM_INCLUDES]);
-            let flags_strict = flags_concat(&[&flags_loose, FLAGS_STRICT_COMMON]);
-            let flags_strictest = flags_concat(&[
-                &flags_strict,
-                FLAGS_STRICTEST_COMMON,
-                FLAGS_CLANG_STRICTEST_EXTRA,
-            ]);
-            (flags_loose, flags_strict, flags_strictest)
-        }
-        Compiler::Gcc => {
-            let flags_loose = flags_concat(&[FLAGS_LOOSE_BASE]);
-            let flags_strict =
-                flags_concat(&[&flags_loose, FLAGS_STRICT_COMMON, FLAGS_GCC_STRICT_EXTRA]);
-            let flags_strictest = flags_concat(&[
-                &flags_strict,
-                FLAGS_STRICTEST_COMMON,
-                FLAGS_GCC_STRICTEST_EXTRA,
-            ]);
-            (flags_loose, flags_strict, flags_strictest)
-        }
+    let dry_run = options.dry_run;
+    let settings = Settings {
+        build_system,
+        author,
+        license,
+        tests: !no_tests,
+        hello: !no_hello,
+        git: !no_git,
+        readme: true,
+        ..options.settings(proj_name.clone(), kind, &defaults, color_enabled)
     };
-
-    let selected_flags = match strictness {
-        Strictness::Loose => flags_loose,
-        Strictness::Strict => flags_strict,
-        Strictness::Strictest => flags_strictest,
+    let actual_cc = settings.cc_command();
+    let mut plan = match build_plan(&settings, &templates, root, color_enabled) {
+        Ok(plan) => plan,
+        Err(err) => {
+            print_err(&err, color_enabled);
+            return ExitCode::from(1);
+        }
     };
-    if let Err(err) = write_file(Path::new("compile_flags.txt"), &selected_flags) {
-        print_err(
-            &format!("failed to write compile_flags.txt: {}", err),
-            color_enabled,
-        );
-        return ExitCode::from(1);
-    }
-
-    if !no_tests {
-        let test_flags = selected_flags.replace(
-            "-Iinclude",
-            // clangd resolves the include directory from within ./tests
-            // isystem ./test-deps avoids generating linting warnings for testing library code
-            FLAGS_TEST_INCLUDE.trim(),
-        );
-        if let Err(err) = write_file(Path::new("tests/compile_flags.txt"), &test_flags) {
-            print_err(
-                &format!("failed to write tests/compile_flags.txt: {}", err),
-                color_enabled,
-            );
+    let summary = match apply_plan(&mut plan, on_conflict, dry_run, &mut input, color_enabled) {
+        Ok(Some(summary)) => summary,
+        Ok(None) => return ExitCode::SUCCESS,
+        Err(err) => {
+            print_err(&err, color_enabled);
             return ExitCode::from(1);
         }
-    }
-
-    let clang_tidy: &[u8] = match linter_strictness {
-        Strictness::Loose => include_bytes!("../assets/clang-tidy-loose.yaml"),
-        Strictness::Strict => include_bytes!("../assets/clang-tidy-strict.yaml"),
-        Strictness::Strictest => include_bytes!("../assets/clang-tidy-strictest.yaml"),
     };
-    if let Err(err) = fs::write(Path::new(".clang-tidy"), clang_tidy) {
-        print_err(
-            &format!("failed to write .clang-tidy: {}", err),
-            color_enabled,
-        );
-        return ExitCode::from(1);
-    }
-
-    let readme_template = include_str!("../assets/README.md");
-    let readme = readme_template.replace("{PROJECT_NAME}", &proj_name);
-    if let Err(err) = write_file(Path::new("README.md"), &readme) {
-        print_err(
-            &format!("failed to write README.md: {}", err),
-            color_enabled,
-        );
-        return ExitCode::from(1);
-    }
 
-    if !no_git && !Path::new(".git").exists() {
-        if Command::new("git")
+    if !no_git
+        && !plan.root().join(".git").exists()
+        && Command::new("git")
             .args(["init", "-q"])
+            .current_dir(plan.root())
             .status()
             .map(|s| s.success())
             .unwrap_or(false)
-        {
-            if let Err(err) = write_file(Path::new(".gitignore"), "target/\n") {
-                print_err(
-                    &format!("failed to write .gitignore: {}", err),
-                    color_enabled,
-                );
-                return ExitCode::from(1);
-            }
-            if !no_commit {
-                let _ = Command::new("git").args(["add", "-A"]).status();
-                let _ = Command::new("git")
-                    .args(["commit", "-m", "init"])
-                    .stdout(std::process::Stdio::null())
-                    .stderr(std::process::Stdio::null())
-                    .status();
-            }
-        }
+        && !no_commit
+    {
+        let _ = Command::new("git")
+            .args(["add", "-A"])
+            .current_dir(plan.root())
+            .status();
+        let _ = Command::new("git")
+            .args(["commit", "-m", "init"])
+            .current_dir(plan.root())
+            .stdout(std::process::Stdio::null())
+            .stderr(std::process::Stdio::null())
+            .status();
     }
 
+    if !summary.only_created() {
+        summary.print(color_enabled);
+        info("");
+    }
     info(&format!(
-        "{} project '{}' at {} (using {})",
+        "{} {} '{}' at {} (using {})",
         green("Created", color_enabled),
+        match kind {
+            ProjectKind::Bin => "project",
+            ProjectKind::Lib => "library project",
+        },
         proj_name,
         proj_path,
         actual_cc
     ));
     info("");
     info("Next steps:");
-    info(&format!(
-        "  make         {}",
-        muted("# debug build", color_enabled)
-    ));
-    info(&format!(
-        "  make run     {}",
-        muted("# build+run", color_enabled)
-    ));
-    info(&format!(
-        "  make watch   {}",
-        muted("# run in watch mode", color_enabled)
-    ));
-    if !no_tests {
-        info(&format!(
-            "  make test    {}",
-            muted("# build and run tests", color_enabled)
-        ));
+    match build_system {
+        BuildSystem::Make => {
+            match kind {
+                ProjectKind::Bin => {
+                    info(&format!(
+                        "  make         {}",
+                        muted("# debug build", color_enabled)
+                    ));
+                    info(&format!(
+                        "  make run     {}",
+                        muted("# build+run", color_enabled)
+                    ));
+                    info(&format!(
+                        "  make watch   {}",
+                        muted("# run in watch mode", color_enabled)
+                    ));
+                }
+                ProjectKind::Lib => {
+                    info(&format!(
+                        "  make         {}",
+                        muted(
+                            &format!("# debug build of lib{0}.a and lib{0}.so", proj_name_lower),
+                            color_enabled
+                        )
+                    ));
+                }
+            }
+            if !no_tests {
+                info(&format!(
+                    "  make test    {}",
+                    muted("# build and run tests", color_enabled)
+                ));
+            }
+            info(&format!(
+                "  make release {}",
+                muted("# release build", color_enabled)
+            ));
+        }
+        BuildSystem::Cmake => {
+            info(&format!(
+                "  cmake -B target/debug     {}",
+                muted("# configure debug build", color_enabled)
+            ));
+            info(&format!(
+                "  cmake --build target/debug {}",
+                muted("# build", color_enabled)
+            ));
+            if !no_tests {
+                info(&format!(
+                    "  ctest --test-dir target/debug {}",
+                    muted("# run tests", color_enabled)
+                ));
+            }
+        }
+        BuildSystem::Meson => {
+            info(&format!(
+                "  CC={} meson setup target/debug {}",
+                actual_cc,
+                muted("# configure debug build", color_enabled)
+            ));
+            info(&format!(
+                "  meson compile -C target/debug {}",
+                muted("# build", color_enabled)
+            ));
+            if !no_tests {
+                info(&format!(
+                    "  meson test -C target/debug {}",
+                    muted("# run tests", color_enabled)
+                ));
+            }
+        }
     }
-    info(&format!(
-        "  make release {}",
-        muted("# release build", color_enabled)
-    ));
     info("\nHappy Hacking!");
 
-    if cfg!(target_os = "macos") && actual_cc.starts_with("gcc") {
+    if cfg!(target_os = "macos") && settings.cc == Compiler::Gcc {
         warn(
             &muted(
                 "Sanitizers may fail with GCC on macOS (ASan runtime missing). Prefer clang for 'make sanitize'.",
@@ -0,0 +1,266 @@
+use crate::gen_tests;
+use crate::plan::Plan;
+use crate::{
+    BuildSystem, CStd, Compiler, Layout, ProjectKind, Settings, Strictness, TestFramework,
+};
+use clap::ValueEnum;
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use toml::{Table, Value};
+
+/// Directory, relative to the project root, where c-init records what it
+/// generated.
+pub const DIR: &str = ".c-init";
+pub const MANIFEST: &str = ".c-init/manifest.toml";
+
+/// Files rebuilt from the project's current state on every run, never merged.
+pub fn derived(rel: &str) -> bool {
+    machine_specific(rel) || rel.starts_with(gen_tests::DIR)
+}
+
+/// Derived files holding this machine's paths (the project directory, header
+/// directories found here), left out of the manifest so it stays the same on
+/// every checkout.
+fn machine_specific(rel: &str) -> bool {
+    matches!(
+        rel,
+        "compile_commands.json" | "compile_flags.txt" | "tests/compile_flags.txt"
+    )
+}
+
+/// Walks up from the current directory to the first one with a manifest.
+pub fn find_root() -> Option<PathBuf> {
+    let current = env::current_dir().ok()?;
+    current
+        .ancestors()
+        .find(|dir| dir.join(MANIFEST).is_file())
+        .map(Path::to_path_buf)
+}
+
+/// `sha256:<hex>` of some file contents.
+pub fn hash(contents: &[u8]) -> String {
+    let digest = Sha256::digest(contents);
+    let hex: String = digest.iter().map(|byte| format!("{:02x}", byte)).collect();
+    format!("sha256:{}", hex)
+}
+
+/// Where the pristine generated copy of `rel` is kept, for three-way merges.
+pub fn base_path(rel: &str) -> String {
+    format!("{}/base/{}", DIR, rel)
+}
+
+/// Adds the manifest (c-init version, options and a hash of every generated
+/// file) to `plan`, along with pristine copies of the generated text files
+/// so a later `c-init upgrade` can tell the user's edits from template
+/// changes.
+pub fn record(plan: &mut Plan, settings: &Settings, templates: &[String]) {
+    let mut files = Table::new();
+    let mut bases = Vec::new();
+    for rel in plan.paths().map(str::to_string).collect::<Vec<_>>() {
+        if rel.starts_with(DIR) || machine_specific(&rel) {
+            continue;
+        }
+        let Some(contents) = plan.contents(&rel) else {
+            continue;
+        };
+        files.insert(rel.clone(), Value::from(hash(contents)));
+        // Vendored test libraries (acutest, `vendor add --test`) are copied verbatim.
+        let merged_on_upgrade = !derived(&rel)
+            && !rel.starts_with("tests/test-deps/")
+            && std::str::from_utf8(contents).is_ok();
+        if merged_on_upgrade {
+            bases.push((base_path(&rel), contents.to_vec()));
+        }
+    }
+    for (rel, contents) in bases {
+        plan.file(&rel, contents);
+    }
+
+    let layout = &settings.layout;
+    let mut options = Table::new();
+    options.insert("name".into(), Value::from(settings.name.as_str()));
+    options.insert("kind".into(), Value::from(settings.kind.as_str()));
+    options.insert(
+        "build_system".into(),
+        Value::from(settings.build_system.as_str()),
+    );
+    options.insert("cc".into(), Value::from(settings.cc.as_str()));
+    if let Some(cc_path) = &settings.cc_path {
+        options.insert("cc_command".into(), Value::from(cc_path.as_str()));
+    }
+    if let Some(target) = &settings.target {
+        options.insert("target".into(), Value::from(target.as_str()));
+    }
+    if let Some(sysroot) = &settings.sysroot {
+        options.insert("sysroot".into(), Value::from(sysroot.as_str()));
+    }
+    if !settings.deps.is_empty() {
+        options.insert("deps".into(), Value::from(settings.deps.clone()));
+    }
+    options.insert(
+        "strictness".into(),
+        Value::from(settings.strictness.as_str()),
+    );
+    options.insert(
+        "linter_strictness".into(),
+        Value::from(settings.linter_strictness.as_str()),
+    );
+    options.insert("std".into(), Value::from(settings.std.as_str()));
+    options.insert(
+        "test_framework".into(),
+        Value::from(settings.test_framework.as_str()),
+    );
+    if settings.coverage_min > 0 {
+        options.insert(
+            "coverage_min".into(),
+            Value::from(i64::from(settings.coverage_min)),
+        );
+    }
+    if let Some(author) = &settings.author {
+        options.insert("author".into(), Value::from(author.as_str()));
+    }
+    if let Some(license) = &settings.license {
+        options.insert("license".into(), Value::from(license.as_str()));
+    }
+    options.insert("tests".into(), Value::from(settings.tests));
+    options.insert("hello".into(), Value::from(settings.hello));
+    options.insert("git".into(), Value::from(settings.git));
+    options.insert("readme".into(), Value::from(settings.readme));
+    options.insert("probe".into(), Value::from(settings.probe));
+    options.insert("src_dirs".into(), Value::from(layout.src_dirs.clone()));
+    options.insert("inc_dirs".into(), Value::from(layout.inc_dirs.clone()));
+    options.insert(
+        "entry_points".into(),
+        Value::from(layout.entry_points.clone()),
+    );
+
+    let mut manifest = Table::new();
+    manifest.insert("version".into(), Value::from(env!("CARGO_PKG_VERSION")));
+    manifest.insert("templates".into(), Value::from(templates.to_vec()));
+    manifest.insert("options".into(), Value::Table(options));
+    manifest.insert("files".into(), Value::Table(files));
+    plan.file(
+        MANIFEST,
+        format!(
+            "# Written by c-init for `c-init upgrade`; commit it with the project.\n{}",
+            manifest
+        ),
+    );
+}
+
+/// A manifest read back from a project.
+pub struct Manifest {
+    /// c-init version that generated the project.
+    pub version: String,
+    /// Template directories the project was generated with.
+    pub templates: Vec<String>,
+    pub settings: Settings,
+    files: BTreeMap<String, String>,
+}
+
+impl Manifest {
+    pub fn load(root: &Path) -> Result<Self, String> {
+        let path = root.join(MANIFEST);
+        let contents = fs::read_to_string(&path)
+            .map_err(|err| format!("failed to read {}: {}", path.display(), err))?;
+        let table = contents
+            .parse::<Table>()
+            .map_err(|err| format!("invalid manifest {}: {}", path.display(), err))?;
+        Self::from_table(&table)
+            .map_err(|err| format!("invalid manifest {}: {}", path.display(), err))
+    }
+
+    fn from_table(table: &Table) -> Result<Self, String> {
+        fn string(table: &Table, key: &str) -> Result<String, String> {
+            table
+                .get(key)
+                .and_then(Value::as_str)
+                .map(str::to_string)
+                .ok_or_else(|| format!("missing string '{}'", key))
+        }
+        fn boolean(table: &Table, key: &str) -> Result<bool, String> {
+            table
+                .get(key)
+                .and_then(Value::as_bool)
+                .ok_or_else(|| format!("missing boolean '{}'", key))
+        }
+        fn strings(table: &Table, key: &str) -> Result<Vec<String>, String> {
+            table
+                .get(key)
+                .and_then(Value::as_array)
+                .and_then(|items| {
+                    items
+                        .iter()
+                        .map(|item| item.as_str().map(str::to_string))
+                        .collect()
+                })
+                .ok_or_else(|| format!("missing string list '{}'", key))
+        }
+        fn choice<T: ValueEnum>(table: &Table, key: &str) -> Result<T, String> {
+            let value = string(table, key)?;
+            T::from_str(&value, true).map_err(|_| format!("invalid {} '{}'", key, value))
+        }
+
+        let options = table
+            .get("options")
+            .and_then(Value::as_table)
+            .ok_or("missing [options]")?;
+        let kind = match string(options, "kind")?.as_str() {
+            "bin" => ProjectKind::Bin,
+            "lib" => ProjectKind::Lib,
+            other => return Err(format!("invalid kind '{}'", other)),
+        };
+        let settings = Settings {
+            name: string(options, "name")?,
+            kind,
+            build_system: choice::<BuildSystem>(options, "build_system")?,
+            cc: choice::<Compiler>(options, "cc")?,
+            cc_path: string(options, "cc_command").ok(),
+            target: string(options, "target").ok(),
+            sysroot: string(options, "sysroot").ok(),
+            deps: strings(options, "deps").unwrap_or_default(),
+            strictness: choice::<Strictness>(options, "strictness")?,
+            linter_strictness: choice::<Strictness>(options, "linter_strictness")?,
+            std: choice::<CStd>(options, "std")?,
+            test_framework: choice::<TestFramework>(options, "test_framework")?,
+            coverage_min: options
+                .get("coverage_min")
+                .and_then(Value::as_integer)
+                .and_then(|percent| u8::try_from(percent).ok())
+                .unwrap_or(0),
+            author: string(options, "author").ok(),
+            license: string(options, "license").ok(),
+            tests: boolean(options, "tests")?,
+            hello: boolean(options, "hello")?,
+            git: boolean(options, "git")?,
+            readme: boolean(options, "readme")?,
+            probe: boolean(options, "probe")?,
+            layout: Layout {
+                src_dirs: strings(options, "src_dirs")?,
+                inc_dirs: strings(options, "inc_dirs")?,
+                entry_points: strings(options, "entry_points")?,
+            },
+        };
+        let files = table
+            .get("files")
+            .and_then(Value::as_table)
+            .ok_or("missing [files]")?
+            .iter()
+            .filter_map(|(rel, hash)| Some((rel.clone(), hash.as_str()?.to_string())))
+            .collect();
+        Ok(Self {
+            version: string(table, "version")?,
+            templates: strings(table, "templates")?,
+            settings,
+            files,
+        })
+    }
+
+    /// Hash of `rel` as it was generated, if c-init generated it.
+    pub fn hash_of(&self, rel: &str) -> Option<&str> {
+        self.files.get(rel).map(String::as_str)
+    }
+}
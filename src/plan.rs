@@ -0,0 +1,706 @@
+use crate::{colorize, green, info, manifest, muted};
+use similar::{DiffOp, TextDiff};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use tempfile::TempDir;
+
+/// Everything a run would create under the project root, built in memory
+/// before anything touches the disk.
+pub struct Plan {
+    root: PathBuf,
+    dirs: Vec<String>,
+    files: Vec<(String, Vec<u8>)>,
+    /// How conflicting files were resolved, in the order they were.
+    resolved: Vec<(String, Outcome)>,
+}
+
+/// What to do with a planned file that would replace different contents.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Resolution {
+    /// Keep the existing file.
+    Skip,
+    Overwrite,
+    /// Keep the existing file as `<file>.orig` and write the new one.
+    Backup,
+    /// Keep both versions, with differing hunks between conflict markers.
+    Merge,
+}
+
+#[derive(Clone, Debug)]
+enum Outcome {
+    Skipped,
+    Overwritten,
+    BackedUp(String),
+    Merged { conflicts: bool },
+}
+
+impl Plan {
+    pub fn new(root: PathBuf) -> Self {
+        Self {
+            root,
+            dirs: Vec::new(),
+            files: Vec::new(),
+            resolved: Vec::new(),
+        }
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Adds an (empty) directory to create.
+    pub fn dir(&mut self, rel: &str) {
+        if !self.dirs.iter().any(|dir| dir == rel) {
+            self.dirs.push(rel.to_string());
+        }
+    }
+
+    /// Adds a file, replacing any earlier entry for the same path.
+    pub fn file(&mut self, rel: &str, contents: impl Into<Vec<u8>>) {
+        let contents = contents.into();
+        match self.files.iter_mut().find(|(path, _)| path == rel) {
+            Some(entry) => entry.1 = contents,
+            None => self.files.push((rel.to_string(), contents)),
+        }
+    }
+
+    /// Paths of the planned files, in the order they were added.
+    pub fn paths(&self) -> impl Iterator<Item = &str> {
+        self.files.iter().map(|(path, _)| path.as_str())
+    }
+
+    fn existing(&self, rel: &str) -> Option<Vec<u8>> {
+        let path = self.root.join(rel);
+        if path.is_file() {
+            fs::read(path).ok()
+        } else {
+            None
+        }
+    }
+
+    /// Planned files that would replace different existing contents.
+    pub fn conflicts(&self) -> Vec<String> {
+        self.files
+            .iter()
+            .filter(|(rel, contents)| {
+                self.existing(rel)
+                    .is_some_and(|existing| existing != *contents)
+            })
+            .map(|(rel, _)| rel.clone())
+            .collect()
+    }
+
+    /// Contents planned for `rel`.
+    pub fn contents(&self, rel: &str) -> Option<&[u8]> {
+        self.files
+            .iter()
+            .find(|(path, _)| path == rel)
+            .map(|(_, contents)| contents.as_slice())
+    }
+
+    /// Applies `resolution` to a conflicting file. Files that cannot be
+    /// merged as text are backed up instead. Skipping also works for files
+    /// that do not exist, leaving them uncreated.
+    pub fn resolve(&mut self, rel: &str, resolution: Resolution) {
+        let Some(index) = self.files.iter().position(|(path, _)| path == rel) else {
+            return;
+        };
+        if resolution == Resolution::Skip {
+            self.files.remove(index);
+            self.resolved.push((rel.to_string(), Outcome::Skipped));
+            return;
+        }
+        let Some(existing) = self.existing(rel) else {
+            return;
+        };
+        let outcome = match resolution {
+            Resolution::Skip => unreachable!("handled above"),
+            Resolution::Overwrite => Outcome::Overwritten,
+            Resolution::Backup => {
+                let mut orig = format!("{}.orig", rel);
+                let mut n = 1;
+                while self.root.join(&orig).exists() || self.paths().any(|path| path == orig) {
+                    orig = format!("{}.orig.{}", rel, n);
+                    n += 1;
+                }
+                self.file(&orig, existing);
+                Outcome::BackedUp(orig)
+            }
+            Resolution::Merge => {
+                let merged = match (
+                    std::str::from_utf8(&existing),
+                    std::str::from_utf8(&self.files[index].1),
+                ) {
+                    (Ok(old), Ok(new)) => merge(old, new),
+                    _ => return self.resolve(rel, Resolution::Backup),
+                };
+                self.files[index].1 = merged.into_bytes();
+                Outcome::Merged { conflicts: true }
+            }
+        };
+        self.resolved.push((rel.to_string(), outcome));
+    }
+
+    /// Three-way merges a conflicting file: the changes from `base` (what
+    /// was generated last time) to the planned contents are applied to the
+    /// existing file, with conflict markers where both changed the same
+    /// lines. Falls back to [`Resolution::Merge`] for non-UTF-8 files.
+    pub fn merge_with_base(&mut self, rel: &str, base: &str) {
+        let Some(existing) = self.existing(rel) else {
+            return;
+        };
+        let Some(index) = self.files.iter().position(|(path, _)| path == rel) else {
+            return;
+        };
+        let (merged, conflicts) = match (
+            std::str::from_utf8(&existing),
+            std::str::from_utf8(&self.files[index].1),
+        ) {
+            (Ok(ours), Ok(theirs)) => merge3(base, ours, theirs),
+            _ => return self.resolve(rel, Resolution::Merge),
+        };
+        self.files[index].1 = merged.into_bytes();
+        self.resolved
+            .push((rel.to_string(), Outcome::Merged { conflicts }));
+    }
+
+    /// What writing the plan will do to each file, for [`Summary::print`].
+    /// Must be taken before the plan is written.
+    pub fn summary(&self) -> Summary {
+        let mut summary = Summary::default();
+        let backups: Vec<&str> = self
+            .resolved
+            .iter()
+            .filter_map(|(_, outcome)| match outcome {
+                Outcome::BackedUp(orig) => Some(orig.as_str()),
+                _ => None,
+            })
+            .collect();
+        for (rel, contents) in &self.files {
+            if backups.contains(&rel.as_str()) || rel.starts_with(manifest::DIR) {
+                continue;
+            }
+            let resolved = self
+                .resolved
+                .iter()
+                .find(|(path, _)| path == rel)
+                .map(|(_, outcome)| outcome);
+            let (group, entry) = match (resolved, self.existing(rel)) {
+                (Some(Outcome::BackedUp(orig)), _) => {
+                    (Group::BackedUp, format!("{} -> {}", rel, orig))
+                }
+                (Some(Outcome::Merged { conflicts: false }), _) => (Group::Merged, rel.clone()),
+                (Some(Outcome::Merged { conflicts: true }), _) => {
+                    (Group::Conflicted, rel.clone())
+                }
+                (_, None) => (Group::Created, rel.clone()),
+                (_, Some(existing)) if existing == *contents => (Group::Unchanged, rel.clone()),
+                (_, Some(_)) => (Group::Overwritten, rel.clone()),
+            };
+            summary.entries.push((group, entry));
+        }
+        for (rel, outcome) in &self.resolved {
+            if matches!(outcome, Outcome::Skipped) {
+                summary.entries.push((Group::Skipped, rel.clone()));
+            }
+        }
+        summary.entries.sort_by_key(|(group, _)| *group);
+        summary
+    }
+
+    /// Writes the plan as one transaction. Everything is staged in a temp
+    /// directory first; a new project directory is then renamed into place
+    /// in one step, while an existing one has each file swapped in with the
+    /// original moved to a backup. If any step fails, every change is undone
+    /// and the target is left exactly as it was.
+    pub fn write(&self) -> Result<(), String> {
+        let mut tx = Transaction::default();
+        self.commit(&mut tx).map_err(|err| {
+            tx.rollback();
+            format!("{} (no changes were made)", err)
+        })
+    }
+
+    fn commit(&self, tx: &mut Transaction) -> Result<(), String> {
+        let root_exists = self.root.is_dir();
+        let stage_parent = if root_exists {
+            self.root.clone()
+        } else {
+            let parent = self.root.parent().unwrap_or(Path::new("/")).to_path_buf();
+            tx.create_dirs(&parent)
+                .map_err(|err| format!("failed to create {}: {}", parent.display(), err))?;
+            parent
+        };
+        let mut builder = tempfile::Builder::new();
+        builder.prefix(".c-init-stage-");
+        // A new project is this directory renamed, so create it with the
+        // usual (umask-governed) mode instead of tempfile's private 0700.
+        #[cfg(unix)]
+        builder.permissions(std::os::unix::fs::PermissionsExt::from_mode(0o777));
+        let stage = builder
+            .tempdir_in(&stage_parent)
+            .map_err(|err| format!("failed to create staging directory: {}", err))?;
+        for dir in &self.dirs {
+            fs::create_dir_all(stage.path().join(dir))
+                .map_err(|err| format!("failed to stage {}: {}", dir, err))?;
+        }
+        for (rel, contents) in &self.files {
+            let path = stage.path().join(rel);
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)
+                    .map_err(|err| format!("failed to stage {}: {}", rel, err))?;
+            }
+            fs::write(&path, contents)
+                .map_err(|err| format!("failed to stage {}: {}", rel, err))?;
+        }
+
+        if !root_exists {
+            let staged = stage.keep();
+            return fs::rename(&staged, &self.root).map_err(|err| {
+                let _ = fs::remove_dir_all(&staged);
+                format!("failed to create {}: {}", self.root.display(), err)
+            });
+        }
+
+        let backup = tx
+            .backup_dir
+            .insert(
+                tempfile::Builder::new()
+                    .prefix(".c-init-backup-")
+                    .tempdir_in(&self.root)
+                    .map_err(|err| format!("failed to create backup directory: {}", err))?,
+            )
+            .path()
+            .to_path_buf();
+        for dir in &self.dirs {
+            tx.create_dirs(&self.root.join(dir))
+                .map_err(|err| format!("failed to create {}: {}", dir, err))?;
+        }
+        for (rel, _) in &self.files {
+            let dest = self.root.join(rel);
+            if let Some(parent) = dest.parent() {
+                tx.create_dirs(parent)
+                    .map_err(|err| format!("failed to create {}: {}", parent.display(), err))?;
+            }
+            if fs::symlink_metadata(&dest).is_ok() {
+                let saved = backup.join(rel);
+                if let Some(parent) = saved.parent() {
+                    fs::create_dir_all(parent)
+                        .map_err(|err| format!("failed to back up {}: {}", rel, err))?;
+                }
+                fs::rename(&dest, &saved)
+                    .map_err(|err| format!("failed to back up {}: {}", rel, err))?;
+                tx.backups.push((dest.clone(), saved));
+            }
+            fs::rename(stage.path().join(rel), &dest)
+                .map_err(|err| format!("failed to write {}: {}", rel, err))?;
+            tx.placed.push(dest);
+        }
+        // Success: nothing to undo. Dropping the transaction deletes the
+        // backed up originals.
+        tx.backups.clear();
+        Ok(())
+    }
+
+    /// Prints the planned tree with file sizes, marking files that already
+    /// exist.
+    pub fn print_tree(&self, color_enabled: bool) {
+        #[derive(Default)]
+        struct Node {
+            children: BTreeMap<String, Node>,
+            /// Index into `Plan::files` for file nodes.
+            file: Option<usize>,
+        }
+        fn insert<'a>(mut node: &'a mut Node, rel: &str) -> &'a mut Node {
+            for part in rel.split('/') {
+                node = node.children.entry(part.to_string()).or_default();
+            }
+            node
+        }
+        let mut tree = Node::default();
+        for dir in &self.dirs {
+            insert(&mut tree, dir);
+        }
+        for (index, (rel, _)) in self.files.iter().enumerate() {
+            insert(&mut tree, rel).file = Some(index);
+        }
+
+        fn print(plan: &Plan, node: &Node, prefix: &str, color_enabled: bool) {
+            let count = node.children.len();
+            for (i, (name, child)) in node.children.iter().enumerate() {
+                let last = i + 1 == count;
+                let branch = if last { "└── " } else { "├── " };
+                match child.file {
+                    Some(index) => {
+                        let (rel, contents) = &plan.files[index];
+                        let status = match plan.existing(rel) {
+                            Some(existing) if existing == *contents => {
+                                muted("unchanged", color_enabled)
+                            }
+                            Some(_) => colorize("overwrite", "33", color_enabled),
+                            None => green("new", color_enabled),
+                        };
+                        info(&format!(
+                            "{}{}{}  {} {}",
+                            prefix,
+                            branch,
+                            name,
+                            muted(&format!("({})", human_size(contents.len())), color_enabled),
+                            status
+                        ));
+                    }
+                    None => {
+                        info(&format!("{}{}{}/", prefix, branch, name));
+                        let nested = format!("{}{}", prefix, if last { "    " } else { "│   " });
+                        print(plan, child, &nested, color_enabled);
+                    }
+                }
+            }
+        }
+
+        let created = if self.root.exists() {
+            String::new()
+        } else {
+            format!(" {}", green("new", color_enabled))
+        };
+        info(&format!("{}/{}", self.root.display(), created));
+        print(self, &tree, "", color_enabled);
+    }
+
+    /// Prints a unified diff for every planned file that would change an
+    /// existing one.
+    pub fn print_diff(&self, color_enabled: bool) {
+        for (rel, contents) in &self.files {
+            let Some(existing) = self.existing(rel) else {
+                continue;
+            };
+            if existing == *contents {
+                continue;
+            }
+            let (Ok(old), Ok(new)) = (
+                std::str::from_utf8(&existing),
+                std::str::from_utf8(contents),
+            ) else {
+                info(&format!("Binary file {} differs", rel));
+                continue;
+            };
+            let diff = TextDiff::from_lines(old, new);
+            let unified = diff
+                .unified_diff()
+                .header(&format!("a/{}", rel), &format!("b/{}", rel))
+                .to_string();
+            for line in unified.lines() {
+                let line = if line.starts_with("+++") || line.starts_with("---") {
+                    colorize(line, "1", color_enabled)
+                } else if line.starts_with('+') {
+                    green(line, color_enabled)
+                } else if line.starts_with('-') {
+                    colorize(line, "31", color_enabled)
+                } else if line.starts_with("@@") {
+                    colorize(line, "36", color_enabled)
+                } else {
+                    line.to_string()
+                };
+                info(&line);
+            }
+        }
+    }
+
+    /// Number of planned files that would replace different existing contents.
+    pub fn overwrites(&self) -> usize {
+        self.conflicts().len()
+    }
+
+    pub fn len(&self) -> usize {
+        self.files.len()
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+enum Group {
+    Created,
+    Overwritten,
+    BackedUp,
+    Merged,
+    Conflicted,
+    Skipped,
+    Unchanged,
+}
+
+/// Per-file outcome of writing a plan into a directory that already had
+/// some of its files.
+#[derive(Default)]
+pub struct Summary {
+    entries: Vec<(Group, String)>,
+}
+
+impl Summary {
+    /// True when every file is new, i.e. there is nothing worth reporting.
+    pub fn only_created(&self) -> bool {
+        self.entries
+            .iter()
+            .all(|(group, _)| *group == Group::Created)
+    }
+
+    /// Number of files left with conflict markers.
+    pub fn conflicts(&self) -> usize {
+        self.entries
+            .iter()
+            .filter(|(group, _)| *group == Group::Conflicted)
+            .count()
+    }
+
+    pub fn print(&self, color_enabled: bool) {
+        for (group, entry) in &self.entries {
+            let label = match group {
+                Group::Created => green("created    ", color_enabled),
+                Group::Overwritten => colorize("overwritten", "33", color_enabled),
+                Group::BackedUp => colorize("backed up  ", "33", color_enabled),
+                Group::Merged => green("merged     ", color_enabled),
+                Group::Conflicted => colorize("conflict   ", "31", color_enabled),
+                Group::Skipped => muted("skipped    ", color_enabled),
+                Group::Unchanged => muted("unchanged  ", color_enabled),
+            };
+            let note = if *group == Group::Conflicted {
+                muted(" (resolve the conflict markers)", color_enabled)
+            } else {
+                String::new()
+            };
+            info(&format!("  {} {}{}", label, entry, note));
+        }
+    }
+}
+
+/// Two-way merge of an existing file with the generated one: shared lines
+/// are kept once and every differing hunk is wrapped in git-style conflict
+/// markers, the existing side first.
+fn merge(existing: &str, generated: &str) -> String {
+    fn push_lines(out: &mut String, lines: &[&str]) {
+        for line in lines {
+            out.push_str(line);
+            if !line.ends_with('\n') {
+                out.push('\n');
+            }
+        }
+    }
+    let diff = TextDiff::from_lines(existing, generated);
+    let (old, new) = (diff.old_slices(), diff.new_slices());
+    let mut out = String::new();
+    let mut ours: Vec<&str> = Vec::new();
+    let mut theirs: Vec<&str> = Vec::new();
+    let flush = |out: &mut String, ours: &mut Vec<&str>, theirs: &mut Vec<&str>| {
+        if ours.is_empty() && theirs.is_empty() {
+            return;
+        }
+        out.push_str("<<<<<<< existing\n");
+        push_lines(out, ours);
+        out.push_str("=======\n");
+        push_lines(out, theirs);
+        out.push_str(">>>>>>> c-init\n");
+        ours.clear();
+        theirs.clear();
+    };
+    for op in diff.ops() {
+        if let DiffOp::Equal { old_index, len, .. } = *op {
+            flush(&mut out, &mut ours, &mut theirs);
+            push_lines(&mut out, &old[old_index..old_index + len]);
+        } else {
+            ours.extend_from_slice(&old[op.old_range()]);
+            theirs.extend_from_slice(&new[op.new_range()]);
+        }
+    }
+    flush(&mut out, &mut ours, &mut theirs);
+    out
+}
+
+/// Lines changed on one side of a three-way merge: `base[start..end]` was
+/// replaced by `lines`.
+struct Hunk<'a> {
+    start: usize,
+    end: usize,
+    lines: &'a [&'a str],
+}
+
+fn hunks<'a>(base: &[&str], side: &'a [&'a str]) -> Vec<Hunk<'a>> {
+    let mut ranges: Vec<(Range<usize>, Range<usize>)> = Vec::new();
+    let mut adjacent = false;
+    for op in similar::capture_diff_slices(similar::Algorithm::Myers, base, side) {
+        if matches!(op, DiffOp::Equal { .. }) {
+            adjacent = false;
+            continue;
+        }
+        let (old, new) = (op.old_range(), op.new_range());
+        match ranges.last_mut() {
+            // a delete right before an insert is one replacement
+            Some((last_old, last_new)) if adjacent => {
+                last_old.end = old.end;
+                last_new.end = new.end;
+            }
+            _ => ranges.push((old, new)),
+        }
+        adjacent = true;
+    }
+    ranges
+        .into_iter()
+        .map(|(old, new)| Hunk {
+            start: old.start,
+            end: old.end,
+            lines: &side[new],
+        })
+        .collect()
+}
+
+/// Three-way merge of line-based text: non-overlapping changes from both
+/// sides are combined, identical changes are taken once, and overlapping
+/// ones are wrapped in conflict markers (existing side first). Returns the merged
+/// text and whether any conflict was left.
+fn merge3(base: &str, ours: &str, theirs: &str) -> (String, bool) {
+    fn split(text: &str) -> Vec<&str> {
+        text.split_inclusive('\n').collect()
+    }
+    fn push_lines(out: &mut String, lines: &[&str]) {
+        for line in lines {
+            out.push_str(line);
+            if !line.ends_with('\n') {
+                out.push('\n');
+            }
+        }
+    }
+    /// One side's version of `base[start..end]`, given its hunks in that range.
+    fn side_text<'a>(
+        base: &[&'a str],
+        start: usize,
+        end: usize,
+        hunks: &[&Hunk<'a>],
+    ) -> Vec<&'a str> {
+        let mut out = Vec::new();
+        let mut pos = start;
+        for hunk in hunks {
+            out.extend_from_slice(&base[pos..hunk.start]);
+            out.extend_from_slice(hunk.lines);
+            pos = hunk.end;
+        }
+        out.extend_from_slice(&base[pos..end]);
+        out
+    }
+
+    let (base_lines, our_lines, their_lines) = (split(base), split(ours), split(theirs));
+    let ours_hunks = hunks(&base_lines, &our_lines);
+    let theirs_hunks = hunks(&base_lines, &their_lines);
+
+    let mut out = String::new();
+    let mut conflicts = false;
+    let mut pos = 0;
+    let (mut i, mut j) = (0, 0);
+    while i < ours_hunks.len() || j < theirs_hunks.len() {
+        // Start a cluster at the earliest hunk, then absorb every hunk from
+        // either side that touches it.
+        let take_ours = j >= theirs_hunks.len()
+            || (i < ours_hunks.len() && ours_hunks[i].start <= theirs_hunks[j].start);
+        let first = if take_ours { &ours_hunks[i] } else { &theirs_hunks[j] };
+        let (start, mut end) = (first.start, first.end);
+        let (mut ours_in, mut theirs_in): (Vec<&Hunk>, Vec<&Hunk>) = (Vec::new(), Vec::new());
+        loop {
+            if i < ours_hunks.len() && ours_hunks[i].start <= end {
+                end = end.max(ours_hunks[i].end);
+                ours_in.push(&ours_hunks[i]);
+                i += 1;
+            } else if j < theirs_hunks.len() && theirs_hunks[j].start <= end {
+                end = end.max(theirs_hunks[j].end);
+                theirs_in.push(&theirs_hunks[j]);
+                j += 1;
+            } else {
+                break;
+            }
+        }
+        push_lines(&mut out, &base_lines[pos..start]);
+        let our_text = side_text(&base_lines, start, end, &ours_in);
+        let their_text = side_text(&base_lines, start, end, &theirs_in);
+        if theirs_in.is_empty() || our_text == their_text {
+            push_lines(&mut out, &our_text);
+        } else if ours_in.is_empty() {
+            push_lines(&mut out, &their_text);
+        } else {
+            conflicts = true;
+            out.push_str("<<<<<<< existing\n");
+            push_lines(&mut out, &our_text);
+            out.push_str("=======\n");
+            push_lines(&mut out, &their_text);
+            out.push_str(">>>>>>> c-init\n");
+        }
+        pos = end;
+    }
+    push_lines(&mut out, &base_lines[pos..]);
+    (out, conflicts)
+}
+
+/// Changes made to an existing directory, in order, so they can be undone.
+#[derive(Default)]
+struct Transaction {
+    /// Holds the originals of replaced files until the transaction ends.
+    backup_dir: Option<TempDir>,
+    created_dirs: Vec<PathBuf>,
+    /// (original location, backup location)
+    backups: Vec<(PathBuf, PathBuf)>,
+    placed: Vec<PathBuf>,
+}
+
+impl Transaction {
+    /// `fs::create_dir_all`, remembering each directory it had to create.
+    fn create_dirs(&mut self, dir: &Path) -> io::Result<()> {
+        let missing: Vec<&Path> = dir
+            .ancestors()
+            .take_while(|ancestor| !ancestor.as_os_str().is_empty() && !ancestor.exists())
+            .collect();
+        for dir in missing.into_iter().rev() {
+            fs::create_dir(dir)?;
+            self.created_dirs.push(dir.to_path_buf());
+        }
+        Ok(())
+    }
+
+    fn rollback(&mut self) {
+        for path in self.placed.drain(..).rev() {
+            let _ = fs::remove_file(path);
+        }
+        for (original, saved) in self.backups.drain(..).rev() {
+            let _ = fs::rename(saved, original);
+        }
+        for dir in self.created_dirs.drain(..).rev() {
+            let _ = fs::remove_dir(dir);
+        }
+    }
+}
+
+fn human_size(bytes: usize) -> String {
+    if bytes < 1024 {
+        format!("{} B", bytes)
+    } else {
+        format!("{:.1} KiB", bytes as f64 / 1024.0)
+    }
+}
+
+/// `path` made absolute without requiring it to exist, resolving symlinks in
+/// the part that does (so it matches what `make`'s `$(CURDIR)` reports).
+pub fn absolute_root(path: &Path) -> io::Result<PathBuf> {
+    let absolute = std::path::absolute(path)?;
+    let mut existing = absolute.as_path();
+    let mut rest = Vec::new();
+    while !existing.exists() {
+        match (existing.parent(), existing.file_name()) {
+            (Some(parent), Some(name)) => {
+                rest.push(name.to_os_string());
+                existing = parent;
+            }
+            _ => break,
+        }
+    }
+    let mut root = existing.canonicalize()?;
+    for name in rest.into_iter().rev() {
+        root.push(name);
+    }
+    Ok(root)
+}
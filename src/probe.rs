@@ -0,0 +1,48 @@
+use std::fs;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+/// The smallest translation unit every warning level accepts: an empty file
+/// already trips `-Wpedantic` ("ISO C requires at least one declaration").
+const PROBE: &str = "typedef int c_init_probe;\n";
+
+/// Flags among `candidates` that `cc` rejects, in order. Each is tried alone
+/// with `-Werror`, which also turns clang's "unknown warning option" into a
+/// failure. Fails when `cc` cannot compile even without extra flags.
+pub fn unsupported(cc: &str, candidates: &[&str]) -> Result<Vec<String>, String> {
+    let dir = tempfile::tempdir().map_err(|err| err.to_string())?;
+    let source = dir.path().join("probe.c");
+    fs::write(&source, PROBE).map_err(|err| err.to_string())?;
+
+    if !accepts(cc, &source, &[])? {
+        return Err(format!("{} cannot compile an empty file", cc));
+    }
+    // Most compilers take everything: one run instead of one per flag.
+    if accepts(cc, &source, candidates)? {
+        return Ok(Vec::new());
+    }
+    let mut rejected: Vec<String> = Vec::new();
+    for flag in candidates {
+        if !rejected.iter().any(|seen| seen == flag) && !accepts(cc, &source, &[flag])? {
+            rejected.push(flag.to_string());
+        }
+    }
+    Ok(rejected)
+}
+
+fn accepts(cc: &str, source: &Path, flags: &[&str]) -> Result<bool, String> {
+    let mut words = cc.split_whitespace();
+    let program = words.next().ok_or("no compiler command")?;
+    let status = Command::new(program)
+        .args(words)
+        .arg("-Werror")
+        .args(flags)
+        .arg("-fsyntax-only")
+        .arg(source)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .map_err(|err| format!("failed to run {}: {}", program, err))?;
+    Ok(status.success())
+}
@@ -0,0 +1,278 @@
+use crate::config;
+use minijinja::{Environment, Error, ErrorKind, UndefinedBehavior, Value};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// Embedded templates, keyed by the (templated) path they render to. Entries
+/// not listed in [`GENERATED`] are partials for `{% include %}`. Every entry
+/// is also reachable as `builtin/<key>`, so a user template can include the
+/// default it replaces.
+const EMBEDDED: &[(&str, &str)] = &[
+    ("Makefile", include_str!("../assets/Makefile")),
+    ("CMakeLists.txt", include_str!("../assets/CMakeLists.txt")),
+    ("meson.build", include_str!("../assets/meson.build")),
+    (
+        "meson_options.txt",
+        include_str!("../assets/meson_options.txt"),
+    ),
+    ("README.md", include_str!("../assets/README.md")),
+    (".clang-tidy", include_str!("../assets/clang-tidy.yaml")),
+    (".gitignore", include_str!("../assets/gitignore")),
+    ("src/main.c", include_str!("../assets/main.c")),
+    ("src/{{ name }}.c", include_str!("../assets/lib.c")),
+    (
+        "include/{{ name }}/{{ name }}.h",
+        include_str!("../assets/lib.h"),
+    ),
+    ("tests/test_basic.c", include_str!("../assets/test_basic.c")),
+    (
+        "clang-tidy-loose.yaml",
+        include_str!("../assets/clang-tidy-loose.yaml"),
+    ),
+    (
+        "clang-tidy-strict.yaml",
+        include_str!("../assets/clang-tidy-strict.yaml"),
+    ),
+    (
+        "clang-tidy-strictest.yaml",
+        include_str!("../assets/clang-tidy-strictest.yaml"),
+    ),
+];
+
+/// Files c-init generates itself. A template directory file rendering to one
+/// of these paths replaces the embedded default; any other file is added to
+/// the project as an extra.
+pub const GENERATED: &[&str] = &[
+    "Makefile",
+    "CMakeLists.txt",
+    "meson.build",
+    "meson_options.txt",
+    "README.md",
+    ".clang-tidy",
+    ".gitignore",
+    "src/main.c",
+    "src/{{ name }}.c",
+    "include/{{ name }}/{{ name }}.h",
+    "tests/test_basic.c",
+];
+
+/// Vendored files, copied verbatim rather than rendered.
+pub const VENDORED: &[&str] = &["tests/test-deps/acutest.h"];
+
+fn embedded(name: &str) -> Option<&'static str> {
+    EMBEDDED
+        .iter()
+        .find(|(key, _)| *key == name)
+        .map(|(_, source)| *source)
+}
+
+/// Template directories, highest priority first: each `--template`, then
+/// `<config dir>/templates/default` when it exists.
+pub struct Templates {
+    /// The `--template` directories, canonicalized.
+    requested: Vec<String>,
+    /// (path relative to the template dir, file on disk), first dir wins.
+    files: Vec<(String, PathBuf)>,
+}
+
+impl Templates {
+    /// Resolves each `--template` argument as a directory, or failing that as
+    /// the name of a directory under `<config dir>/templates`.
+    pub fn load(requested: &[String]) -> Result<Self, String> {
+        let user_dir = config::config_dir().map(|dir| dir.join("templates"));
+        let mut dirs = Vec::new();
+        for template in requested {
+            let as_path = PathBuf::from(template);
+            let dir = if as_path.is_dir() {
+                as_path
+            } else if let Some(named) = user_dir.as_ref().map(|dir| dir.join(template))
+                && named.is_dir()
+            {
+                named
+            } else {
+                return Err(format!("template '{}' not found", template));
+            };
+            let dir = dir
+                .canonicalize()
+                .map_err(|err| format!("failed to read template {}: {}", template, err))?;
+            dirs.push(dir);
+        }
+        let requested = dirs
+            .iter()
+            .map(|dir| dir.to_string_lossy().into_owned())
+            .collect();
+        if let Some(default) = user_dir.map(|dir| dir.join("default"))
+            && default.is_dir()
+        {
+            dirs.push(default);
+        }
+
+        let mut files: Vec<(String, PathBuf)> = Vec::new();
+        for dir in &dirs {
+            let mut found = Vec::new();
+            collect(dir, dir, &mut found)
+                .map_err(|err| format!("failed to read template {}: {}", dir.display(), err))?;
+            found.sort();
+            for (rel, path) in found {
+                if !files.iter().any(|(existing, _)| *existing == rel) {
+                    files.push((rel, path));
+                }
+            }
+        }
+        Ok(Self { requested, files })
+    }
+
+    /// The `--template` directories as absolute paths, for the manifest.
+    pub fn requested(&self) -> &[String] {
+        &self.requested
+    }
+}
+
+/// Renders templates against one project's context.
+pub struct Renderer {
+    env: Environment<'static>,
+    ctx: Value,
+    /// User template files: (name in the environment, rendered output path, file on disk).
+    user: Vec<(String, String, PathBuf)>,
+}
+
+impl Renderer {
+    pub fn new(templates: &Templates, ctx: Value) -> Result<Self, String> {
+        let mut env = Environment::new();
+        env.set_trim_blocks(true);
+        env.set_lstrip_blocks(true);
+        env.set_keep_trailing_newline(true);
+        env.set_undefined_behavior(UndefinedBehavior::Strict);
+
+        let sources: Arc<Vec<(String, PathBuf)>> = Arc::new(templates.files.clone());
+        let loader_sources = Arc::clone(&sources);
+        env.set_loader(move |name| {
+            if let Some(builtin) = name.strip_prefix("builtin/") {
+                return Ok(embedded(builtin).map(str::to_string));
+            }
+            if let Some((_, path)) = loader_sources.iter().find(|(rel, _)| rel == name) {
+                return fs::read_to_string(path).map(Some).map_err(|err| {
+                    Error::new(
+                        ErrorKind::TemplateNotFound,
+                        format!("failed to read {}: {}", path.display(), err),
+                    )
+                });
+            }
+            Ok(embedded(name).map(str::to_string))
+        });
+
+        let mut renderer = Self {
+            env,
+            ctx,
+            user: Vec::new(),
+        };
+        for (rel, path) in sources.iter() {
+            let output = renderer.render_path(rel)?;
+            renderer.user.push((rel.clone(), output, path.clone()));
+        }
+        Ok(renderer)
+    }
+
+    /// Renders a templated output path such as `src/{{ name }}.c`.
+    fn render_path(&self, path: &str) -> Result<String, String> {
+        self.env
+            .render_str(path, &self.ctx)
+            .map_err(|err| format!("failed to render path {}: {}", path, self.describe(&err)))
+    }
+
+    /// Renders one of the [`GENERATED`] files, preferring a user template
+    /// that renders to the same path. Returns the output path and contents.
+    pub fn render(&self, key: &str) -> Result<(String, String), String> {
+        let output = self.render_path(key)?;
+        let name = self
+            .user
+            .iter()
+            .find(|(_, user_output, _)| *user_output == output)
+            .map_or(key, |(name, _, _)| name.as_str());
+        let contents = self
+            .env
+            .get_template(name)
+            .and_then(|template| template.render(&self.ctx))
+            .map_err(|err| format!("failed to render {}", self.describe(&err)))?;
+        Ok((output, contents))
+    }
+
+    /// A user-supplied replacement for one of the [`VENDORED`] files.
+    pub fn vendored(&self, rel: &str) -> Option<&Path> {
+        self.user
+            .iter()
+            .find(|(_, output, _)| output == rel)
+            .map(|(_, _, path)| path.as_path())
+    }
+
+    /// Renders every user template file that is neither a [`GENERATED`] or
+    /// [`VENDORED`] override nor a replacement for an embedded partial.
+    /// Files that are not UTF-8 are copied as-is.
+    pub fn extras(&self) -> Result<Vec<(String, Vec<u8>)>, String> {
+        let mut known = Vec::new();
+        for key in GENERATED.iter().chain(VENDORED) {
+            known.push(self.render_path(key)?);
+        }
+        let mut extras = Vec::new();
+        for (name, output, path) in &self.user {
+            if known.contains(output) || embedded(name).is_some() {
+                continue;
+            }
+            let bytes = fs::read(path)
+                .map_err(|err| format!("failed to read {}: {}", path.display(), err))?;
+            let contents = match String::from_utf8(bytes) {
+                Ok(_) => self
+                    .env
+                    .get_template(name)
+                    .and_then(|template| template.render(&self.ctx))
+                    .map(String::into_bytes)
+                    .map_err(|err| format!("failed to render {}", self.describe(&err)))?,
+                Err(err) => err.into_bytes(),
+            };
+            extras.push((output.clone(), contents));
+        }
+        Ok(extras)
+    }
+
+    /// `<file>:<line>: <problem>`, naming the file on disk for user templates.
+    fn describe(&self, err: &Error) -> String {
+        let mut out = String::new();
+        if let Some(name) = err.name() {
+            match self.user.iter().find(|(user_name, _, _)| user_name == name) {
+                Some((_, _, path)) => out.push_str(&path.display().to_string()),
+                None => out.push_str(&format!("builtin/{}", name)),
+            }
+            if let Some(line) = err.line() {
+                out.push_str(&format!(":{}", line));
+            }
+            out.push_str(": ");
+        }
+        out.push_str(&err.kind().to_string());
+        if let Some(detail) = err.detail() {
+            out.push_str(&format!(": {}", detail));
+        }
+        out
+    }
+}
+
+fn collect(root: &Path, dir: &Path, out: &mut Vec<(String, PathBuf)>) -> io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.file_name().is_some_and(|name| name == ".git") {
+            continue;
+        }
+        if path.is_dir() {
+            collect(root, &path, out)?;
+        } else if let Ok(rel) = path.strip_prefix(root) {
+            let rel = rel
+                .components()
+                .map(|c| c.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/");
+            out.push((rel, path));
+        }
+    }
+    Ok(())
+}
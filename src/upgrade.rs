@@ -0,0 +1,174 @@
+use crate::manifest::{self, Manifest};
+use crate::vendor::{self, Vendored};
+use crate::{
+    DryRun, OnConflict, apply_plan, build_plan_with, green, info, plan, print_err, templates, warn,
+};
+use clap::Args;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+#[derive(Debug, Args)]
+pub struct UpgradeArgs {
+    /// Project to upgrade (defaults to the one containing the current directory)
+    path: Option<String>,
+
+    /// Print what would change without writing: tree | diff (against existing files)
+    #[arg(long, value_enum, num_args = 0..=1, require_equals = true, default_missing_value = "tree")]
+    dry_run: Option<DryRun>,
+}
+
+pub fn run(args: UpgradeArgs, color_enabled: bool) -> ExitCode {
+    let root = match &args.path {
+        Some(path) => PathBuf::from(path),
+        None => match manifest::find_root() {
+            Some(root) => root,
+            None => {
+                print_err(
+                    &format!(
+                        "no {} found here or in any parent directory",
+                        manifest::MANIFEST
+                    ),
+                    color_enabled,
+                );
+                return ExitCode::from(1);
+            }
+        },
+    };
+    let root = match plan::absolute_root(&root) {
+        Ok(root) => root,
+        Err(err) => {
+            print_err(
+                &format!("failed to resolve {}: {}", root.display(), err),
+                color_enabled,
+            );
+            return ExitCode::from(1);
+        }
+    };
+    let manifest = match Manifest::load(&root) {
+        Ok(manifest) => manifest,
+        Err(err) => {
+            print_err(&err, color_enabled);
+            return ExitCode::from(1);
+        }
+    };
+    let summary = match regenerate(&root, &manifest, args.dry_run, color_enabled) {
+        Ok(Some(summary)) => summary,
+        Ok(None) => return ExitCode::SUCCESS,
+        Err(err) => {
+            print_err(&err, color_enabled);
+            return ExitCode::from(1);
+        }
+    };
+
+    summary.print(color_enabled);
+    info("");
+    info(&format!(
+        "{} '{}' from c-init {} to {}",
+        green("Upgraded", color_enabled),
+        manifest.settings.name,
+        manifest.version,
+        env!("CARGO_PKG_VERSION")
+    ));
+    warn_conflicts(&summary, color_enabled);
+    ExitCode::SUCCESS
+}
+
+/// Renders the project at `root` from `manifest`'s settings with the current
+/// templates, keeping the user's edits: untouched files are replaced, edited
+/// ones are merged with what was last generated. Returns `None` for a dry run.
+pub fn regenerate(
+    root: &Path,
+    manifest: &Manifest,
+    dry_run: Option<DryRun>,
+    color_enabled: bool,
+) -> Result<Option<plan::Summary>, String> {
+    let vendored = vendor::load(root)?;
+    regenerate_with(root, manifest, vendored, Vec::new(), dry_run, color_enabled)
+}
+
+/// [`regenerate`] with `vendored` in place of the dependencies in the
+/// lockfile, also writing `staged` (contents by path from the root, replacing
+/// what is there) in the same transaction: if anything fails, none of it is
+/// written.
+pub fn regenerate_with(
+    root: &Path,
+    manifest: &Manifest,
+    vendored: Vec<Vendored>,
+    staged: vendor::Files,
+    dry_run: Option<DryRun>,
+    color_enabled: bool,
+) -> Result<Option<plan::Summary>, String> {
+    let templates = templates::Templates::load(&manifest.templates)?;
+    let mut plan = build_plan_with(
+        &manifest.settings,
+        &templates,
+        root.to_path_buf(),
+        vendored,
+        color_enabled,
+    )?;
+
+    let paths: Vec<String> = plan
+        .paths()
+        .filter(|rel| !rel.starts_with(manifest::DIR))
+        .map(str::to_string)
+        .collect();
+    for rel in paths {
+        let Ok(current) = fs::read(root.join(&rel)) else {
+            // Deleted since it was generated: the user doesn't want it back.
+            if manifest.hash_of(&rel).is_some() {
+                plan.resolve(&rel, plan::Resolution::Skip);
+            }
+            continue;
+        };
+        if manifest::derived(&rel) {
+            continue;
+        }
+        let Some(generated) = manifest.hash_of(&rel) else {
+            // The user's own file at a path c-init now generates.
+            plan.resolve(&rel, plan::Resolution::Backup);
+            continue;
+        };
+        if manifest::hash(&current) == generated {
+            continue;
+        }
+        let template_changed = plan
+            .contents(&rel)
+            .is_some_and(|new| manifest::hash(new) != generated);
+        if !template_changed {
+            plan.resolve(&rel, plan::Resolution::Skip);
+            continue;
+        }
+        match fs::read_to_string(root.join(manifest::base_path(&rel))) {
+            Ok(base) if manifest::hash(base.as_bytes()) == generated => {
+                plan.merge_with_base(&rel, &base)
+            }
+            _ => plan.resolve(&rel, plan::Resolution::Merge),
+        }
+    }
+
+    for (rel, contents) in staged {
+        plan.file(&rel, contents);
+    }
+    // Whatever still differs was never edited: take the new version.
+    apply_plan(
+        &mut plan,
+        OnConflict::Overwrite,
+        dry_run,
+        &mut None,
+        color_enabled,
+    )
+}
+
+/// Points at files left with conflict markers by `regenerate`.
+pub fn warn_conflicts(summary: &plan::Summary, color_enabled: bool) {
+    if summary.conflicts() > 0 {
+        warn(
+            &format!(
+                "{} file(s) have conflict markers: edited lines that the new templates also change",
+                summary.conflicts()
+            ),
+            color_enabled,
+        );
+    }
+}
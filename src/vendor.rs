@@ -0,0 +1,614 @@
+use crate::manifest::{self, Manifest};
+use crate::{colorize, green, info, muted, print_err, upgrade};
+use clap::{ArgAction, Subcommand};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::{Command, ExitCode, Stdio};
+use toml::{Table, Value};
+
+/// Records every vendored dependency, relative to the project root.
+pub const LOCKFILE: &str = "c-init.lock";
+
+/// More C files than this is not a "small" library: the user picks with --file.
+const MAX_FILES: usize = 32;
+
+#[derive(Debug, Subcommand)]
+pub enum VendorCommand {
+    /// Copy a single-header or small source library into the project
+    Add {
+        /// Name of the dependency, used as its directory name
+        name: String,
+
+        /// File, directory or local git repository to copy from
+        #[arg(long, value_name = "PATH")]
+        from: String,
+
+        /// Git revision to copy (default: HEAD)
+        #[arg(long, value_name = "REV")]
+        rev: Option<String>,
+
+        /// File to copy, relative to the source (repeatable; default: every .c and .h)
+        #[arg(long = "file", value_name = "PATH")]
+        files: Vec<String>,
+
+        /// Vendor into tests/test-deps/ for the tests only
+        #[arg(long, action = ArgAction::SetTrue)]
+        test: bool,
+    },
+    /// Check vendored files against the lockfile
+    Verify,
+    /// Copy dependencies again from their source (default: all of them)
+    Update {
+        /// Dependencies to update
+        names: Vec<String>,
+
+        /// Git revision to move to (default: the source's HEAD)
+        #[arg(long, value_name = "REV")]
+        rev: Option<String>,
+
+        /// Overwrite vendored files that were changed locally
+        #[arg(short = 'f', long, action = ArgAction::SetTrue)]
+        force: bool,
+    },
+}
+
+/// A dependency copied into the project, as recorded in the lockfile.
+pub struct Vendored {
+    pub name: String,
+    /// File, directory or git repository copied from: absolute as given to
+    /// `--from`, else relative to the project root.
+    pub source: String,
+    /// Commit the files came from, for git sources.
+    pub revision: Option<String>,
+    /// Only the tests use it.
+    pub test: bool,
+    /// `sha256:` hash of every copied file, by path within `dir()`.
+    pub files: BTreeMap<String, String>,
+}
+
+impl Vendored {
+    /// Where the files live, relative to the project root.
+    pub fn dir(&self) -> String {
+        if self.test {
+            format!("tests/test-deps/{}", self.name)
+        } else {
+            format!("deps/{}", self.name)
+        }
+    }
+
+    /// Directory to add to the include path: the library's include/ when it
+    /// has one.
+    pub fn include_dir(&self) -> String {
+        if self.files.keys().any(|rel| rel.starts_with("include/")) {
+            format!("{}/include", self.dir())
+        } else {
+            self.dir()
+        }
+    }
+
+    /// Sources to compile along with the project, relative to the project root.
+    pub fn sources(&self) -> Vec<String> {
+        self.files
+            .keys()
+            .filter(|rel| rel.ends_with(".c"))
+            .map(|rel| format!("{}/{}", self.dir(), rel))
+            .collect()
+    }
+
+    /// Files that no longer match the lockfile, each with what is wrong.
+    fn tampered(&self, root: &Path) -> Vec<(String, &'static str)> {
+        let dir = root.join(self.dir());
+        let mut problems: Vec<(String, &'static str)> = self
+            .files
+            .iter()
+            .filter_map(|(rel, hash)| match fs::read(dir.join(rel)) {
+                Ok(contents) if manifest::hash(&contents) == *hash => None,
+                Ok(_) => Some((rel.clone(), "modified")),
+                Err(_) => Some((rel.clone(), "missing")),
+            })
+            .collect();
+        if let Ok(present) = c_files(&dir) {
+            problems.extend(
+                present
+                    .into_iter()
+                    .filter(|rel| !self.files.contains_key(rel))
+                    .map(|rel| (rel, "not in the lockfile")),
+            );
+        }
+        problems
+    }
+}
+
+/// Dependencies recorded in the project's lockfile; none when it has no
+/// lockfile yet.
+pub fn load(root: &Path) -> Result<Vec<Vendored>, String> {
+    let path = root.join(LOCKFILE);
+    let contents = match fs::read_to_string(&path) {
+        Ok(contents) => contents,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => return Err(format!("failed to read {}: {}", path.display(), err)),
+    };
+    let invalid = |what: String| format!("invalid lockfile {}: {}", path.display(), what);
+    let table = contents
+        .parse::<Table>()
+        .map_err(|err| invalid(err.to_string()))?;
+    let Some(deps) = table.get("deps").and_then(Value::as_table) else {
+        return Ok(Vec::new());
+    };
+    deps.iter()
+        .map(|(name, entry)| {
+            let entry = entry
+                .as_table()
+                .ok_or_else(|| invalid(format!("'{}' is not a table", name)))?;
+            let string = |key: &str| entry.get(key).and_then(Value::as_str).map(str::to_string);
+            Ok(Vendored {
+                name: name.clone(),
+                source: string("source")
+                    .ok_or_else(|| invalid(format!("'{}' has no source", name)))?,
+                revision: string("revision"),
+                test: entry.get("test").and_then(Value::as_bool).unwrap_or(false),
+                files: entry
+                    .get("files")
+                    .and_then(Value::as_table)
+                    .ok_or_else(|| invalid(format!("'{}' has no files", name)))?
+                    .iter()
+                    .filter_map(|(rel, hash)| Some((rel.clone(), hash.as_str()?.to_string())))
+                    .collect(),
+            })
+        })
+        .collect()
+}
+
+/// Contents of the lockfile recording `vendored`.
+fn lockfile(vendored: &[Vendored]) -> String {
+    let mut deps = Table::new();
+    for dep in vendored {
+        let mut entry = Table::new();
+        entry.insert("source".into(), Value::from(dep.source.as_str()));
+        if let Some(revision) = &dep.revision {
+            entry.insert("revision".into(), Value::from(revision.as_str()));
+        }
+        entry.insert("test".into(), Value::from(dep.test));
+        let files: Table = dep
+            .files
+            .iter()
+            .map(|(rel, hash)| (rel.clone(), Value::from(hash.as_str())))
+            .collect();
+        entry.insert("files".into(), Value::Table(files));
+        deps.insert(dep.name.clone(), Value::Table(entry));
+    }
+    let mut table = Table::new();
+    table.insert("deps".into(), Value::Table(deps));
+    format!(
+        "# Written by `c-init vendor`; commit it with the project.\n{}",
+        table
+    )
+}
+
+/// `.c` and `.h` files under `dir`, relative to it, skipping hidden entries.
+fn c_files(dir: &Path) -> io::Result<Vec<String>> {
+    fn walk(dir: &Path, prefix: &str, found: &mut Vec<String>) -> io::Result<()> {
+        for entry in fs::read_dir(dir)? {
+            let entry = entry?;
+            let name = entry.file_name().to_string_lossy().into_owned();
+            if name.starts_with('.') {
+                continue;
+            }
+            let rel = format!("{}{}", prefix, name);
+            if entry.file_type()?.is_dir() {
+                walk(&entry.path(), &format!("{}/", rel), found)?;
+            } else if is_c_file(&rel) {
+                found.push(rel);
+            }
+        }
+        Ok(())
+    }
+    let mut found = Vec::new();
+    walk(dir, "", &mut found)?;
+    found.sort();
+    Ok(found)
+}
+
+fn is_c_file(rel: &str) -> bool {
+    rel.ends_with(".c") || rel.ends_with(".h")
+}
+
+fn git(repo: &Path, args: &[&str]) -> Result<Vec<u8>, String> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(repo)
+        .args(args)
+        .stdin(Stdio::null())
+        .output()
+        .map_err(|err| format!("failed to run git: {}", err))?;
+    if !output.status.success() {
+        return Err(format!(
+            "git {} failed in {}: {}",
+            args.join(" "),
+            repo.display(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(output.stdout)
+}
+
+/// File contents by relative path.
+pub type Files = Vec<(String, Vec<u8>)>;
+
+/// Copied files by path within the dependency, and the commit they came from.
+struct Fetched {
+    files: Files,
+    revision: Option<String>,
+}
+
+/// Reads the files to vendor from `source`: a single file, a directory, or a
+/// git repository at `rev`. `only` picks files; by default every `.c` and `.h`.
+fn fetch(source: &Path, rev: Option<&str>, only: &[String]) -> Result<Fetched, String> {
+    let is_repo = source.join(".git").exists();
+    if rev.is_some() && !is_repo {
+        return Err(format!(
+            "--rev needs a git repository, and {} is not one",
+            source.display()
+        ));
+    }
+    if source.is_file() {
+        if !only.is_empty() {
+            return Err("--file only applies to directories and repositories".into());
+        }
+        let name = source
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let contents = fs::read(source)
+            .map_err(|err| format!("failed to read {}: {}", source.display(), err))?;
+        return Ok(Fetched {
+            files: vec![(name, contents)],
+            revision: None,
+        });
+    }
+    if !source.is_dir() {
+        return Err(format!("{} does not exist", source.display()));
+    }
+
+    let (available, revision) = if is_repo {
+        let commit = git(
+            source,
+            &[
+                "rev-parse",
+                "--verify",
+                &format!("{}^{{commit}}", rev.unwrap_or("HEAD")),
+            ],
+        )?;
+        let commit = String::from_utf8_lossy(&commit).trim().to_string();
+        let listing = git(source, &["ls-tree", "-r", "--name-only", "-z", &commit])?;
+        let available: Vec<String> = listing
+            .split(|byte| *byte == 0)
+            .filter(|path| !path.is_empty())
+            .map(|path| String::from_utf8_lossy(path).into_owned())
+            .collect();
+        (available, Some(commit))
+    } else {
+        let available = c_files(source)
+            .map_err(|err| format!("failed to read {}: {}", source.display(), err))?;
+        (available, None)
+    };
+
+    let picked: Vec<String> = if only.is_empty() {
+        let picked: Vec<String> = available.into_iter().filter(|rel| is_c_file(rel)).collect();
+        if picked.is_empty() {
+            return Err(format!("no .c or .h files in {}", source.display()));
+        }
+        if picked.len() > MAX_FILES {
+            return Err(format!(
+                "{} has {} .c/.h files; choose the ones to vendor with --file",
+                source.display(),
+                picked.len()
+            ));
+        }
+        picked
+    } else {
+        let wanted: Vec<String> = only
+            .iter()
+            .map(|rel| rel.trim_start_matches("./").to_string())
+            .collect();
+        let exists = |rel: &String| {
+            if is_repo {
+                available.contains(rel)
+            } else {
+                source.join(rel).is_file()
+            }
+        };
+        if let Some(missing) = wanted.iter().find(|rel| !exists(rel)) {
+            return Err(format!("{} not found in {}", missing, source.display()));
+        }
+        wanted
+    };
+
+    let files = picked
+        .into_iter()
+        .map(|rel| {
+            let contents = match &revision {
+                Some(commit) => git(source, &["show", &format!("{}:{}", commit, rel)])?,
+                None => fs::read(source.join(&rel))
+                    .map_err(|err| format!("failed to read {}: {}", rel, err))?,
+            };
+            Ok((rel, contents))
+        })
+        .collect::<Result<_, String>>()?;
+    Ok(Fetched { files, revision })
+}
+
+/// Records `fetched` as the files of `dep`, adding them to `staged` by path
+/// from the project root.
+fn install(dep: &mut Vendored, fetched: Fetched, staged: &mut Files) {
+    dep.files.clear();
+    for (rel, contents) in fetched.files {
+        dep.files.insert(rel.clone(), manifest::hash(&contents));
+        staged.push((format!("{}/{}", dep.dir(), rel), contents));
+    }
+    dep.revision = fetched.revision;
+}
+
+/// `path` relative to `base`, both absolute and free of `.` and `..`.
+fn relative_to(base: &Path, path: &Path) -> PathBuf {
+    let base: Vec<_> = base.components().collect();
+    let path: Vec<_> = path.components().collect();
+    let common = base.iter().zip(&path).take_while(|(a, b)| a == b).count();
+    let mut rel: PathBuf = base[common..].iter().map(|_| "..").collect();
+    rel.extend(&path[common..]);
+    if rel.as_os_str().is_empty() {
+        rel.push(".");
+    }
+    rel
+}
+
+/// First 12 characters of a commit, or where the files came from otherwise.
+fn short_revision(dep: &Vendored) -> String {
+    match &dep.revision {
+        Some(commit) => commit.chars().take(12).collect(),
+        None => "unversioned".to_string(),
+    }
+}
+
+pub fn run(command: VendorCommand, color_enabled: bool) -> ExitCode {
+    let Some(root) = manifest::find_root() else {
+        print_err(
+            &format!(
+                "no {} found here or in any parent directory (vendor needs a project generated or adopted by c-init)",
+                manifest::MANIFEST
+            ),
+            color_enabled,
+        );
+        return ExitCode::from(1);
+    };
+    let result = match command {
+        VendorCommand::Add {
+            name,
+            from,
+            rev,
+            files,
+            test,
+        } => add(
+            &root,
+            &name,
+            &from,
+            rev.as_deref(),
+            &files,
+            test,
+            color_enabled,
+        ),
+        VendorCommand::Verify => verify(&root, color_enabled),
+        VendorCommand::Update { names, rev, force } => {
+            update(&root, &names, rev.as_deref(), force, color_enabled)
+        }
+    };
+    match result {
+        Ok(code) => code,
+        Err(err) => {
+            print_err(&err, color_enabled);
+            ExitCode::from(1)
+        }
+    }
+}
+
+/// Writes the `staged` files and the lockfile recording `vendored`, with the
+/// build files and both compile_flags.txt regenerated for them the way
+/// `c-init upgrade` does, all in one transaction.
+fn rewire(
+    root: &Path,
+    vendored: Vec<Vendored>,
+    mut staged: Files,
+    color_enabled: bool,
+) -> Result<(), String> {
+    let manifest = Manifest::load(root)?;
+    staged.push((LOCKFILE.to_string(), lockfile(&vendored).into_bytes()));
+    if let Some(summary) =
+        upgrade::regenerate_with(root, &manifest, vendored, staged, None, color_enabled)?
+    {
+        summary.print(color_enabled);
+        upgrade::warn_conflicts(&summary, color_enabled);
+    }
+    Ok(())
+}
+
+fn add(
+    root: &Path,
+    name: &str,
+    from: &str,
+    rev: Option<&str>,
+    only: &[String],
+    test: bool,
+    color_enabled: bool,
+) -> Result<ExitCode, String> {
+    let valid_name = !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.'))
+        && !name.starts_with('.');
+    if !valid_name {
+        return Err(format!(
+            "invalid dependency name '{}' (use letters, digits, '-', '_' and '.')",
+            name
+        ));
+    }
+    if test && !root.join("tests").is_dir() {
+        return Err("--test needs a project with tests".into());
+    }
+    let mut vendored = load(root)?;
+    if vendored.iter().any(|dep| dep.name == name) {
+        return Err(format!(
+            "'{}' is already vendored (use c-init vendor update)",
+            name
+        ));
+    }
+    let source: PathBuf =
+        fs::canonicalize(from).map_err(|err| format!("cannot read {}: {}", from, err))?;
+    // The lockfile is committed, so it only keeps a path from this machine
+    // when the user gave one.
+    let recorded = if Path::new(from).is_absolute() {
+        from.to_string()
+    } else {
+        let root = fs::canonicalize(root)
+            .map_err(|err| format!("failed to resolve {}: {}", root.display(), err))?;
+        relative_to(&root, &source).to_string_lossy().into_owned()
+    };
+    let mut dep = Vendored {
+        name: name.to_string(),
+        source: recorded,
+        revision: None,
+        test,
+        files: BTreeMap::new(),
+    };
+    if root.join(dep.dir()).exists() {
+        return Err(format!("{} already exists", dep.dir()));
+    }
+    let fetched = fetch(&source, rev, only)?;
+    let mut staged = Files::new();
+    install(&mut dep, fetched, &mut staged);
+    let summary = format!(
+        "{} '{}' ({}) into {}",
+        green("Vendored", color_enabled),
+        name,
+        short_revision(&dep),
+        dep.dir()
+    );
+    vendored.push(dep);
+    vendored.sort_by(|a, b| a.name.cmp(&b.name));
+    rewire(root, vendored, staged, color_enabled)?;
+    info("");
+    info(&summary);
+    Ok(ExitCode::SUCCESS)
+}
+
+fn verify(root: &Path, color_enabled: bool) -> Result<ExitCode, String> {
+    let vendored = load(root)?;
+    if vendored.is_empty() {
+        info(&format!(
+            "Nothing vendored ({} not found or empty)",
+            LOCKFILE
+        ));
+        return Ok(ExitCode::SUCCESS);
+    }
+    let mut problems = 0;
+    for dep in &vendored {
+        let tampered = dep.tampered(root);
+        if tampered.is_empty() {
+            info(&format!(
+                "  {} {} {}",
+                green("✔", color_enabled),
+                dep.name,
+                muted(
+                    &format!("{} file(s), {}", dep.files.len(), short_revision(dep)),
+                    color_enabled
+                )
+            ));
+            continue;
+        }
+        info(&format!(
+            "  {} {}",
+            colorize("✘", "31", color_enabled),
+            dep.name
+        ));
+        for (rel, problem) in &tampered {
+            info(&format!("      {}/{}: {}", dep.dir(), rel, problem));
+        }
+        problems += tampered.len();
+    }
+    info("");
+    if problems > 0 {
+        info(&format!(
+            "{} file(s) differ from {}; `c-init vendor update --force` copies the recorded files again",
+            problems, LOCKFILE
+        ));
+        return Ok(ExitCode::from(1));
+    }
+    info(&format!("All vendored files match {}", LOCKFILE));
+    Ok(ExitCode::SUCCESS)
+}
+
+fn update(
+    root: &Path,
+    names: &[String],
+    rev: Option<&str>,
+    force: bool,
+    color_enabled: bool,
+) -> Result<ExitCode, String> {
+    let mut vendored = load(root)?;
+    if let Some(unknown) = names
+        .iter()
+        .find(|name| !vendored.iter().any(|dep| &dep.name == *name))
+    {
+        return Err(format!("'{}' is not vendored", unknown));
+    }
+    if rev.is_some() && names.len() != 1 {
+        return Err("--rev needs exactly one dependency name".into());
+    }
+    let selected = |dep: &Vendored| names.is_empty() || names.contains(&dep.name);
+    if !force {
+        for dep in vendored.iter().filter(|dep| selected(dep)) {
+            if let Some((rel, problem)) = dep.tampered(root).first() {
+                return Err(format!(
+                    "{}/{} is {}; keep your changes elsewhere, or pass --force to discard them",
+                    dep.dir(),
+                    rel,
+                    problem
+                ));
+            }
+        }
+    }
+    let mut staged = Files::new();
+    let mut lines = Vec::new();
+    for dep in vendored.iter_mut().filter(|dep| selected(dep)) {
+        let before = dep.files.clone();
+        let old_revision = short_revision(dep);
+        // The same files as before, from wherever the source is now.
+        // Keeps an absolute source as it is.
+        let source = root.join(&dep.source);
+        let only: Vec<String> = if source.is_file() {
+            Vec::new()
+        } else {
+            dep.files.keys().cloned().collect()
+        };
+        let fetched = fetch(&source, rev, &only)?;
+        install(dep, fetched, &mut staged);
+        lines.push(if dep.files == before {
+            format!("  {} {}", muted("unchanged", color_enabled), dep.name)
+        } else {
+            format!(
+                "  {} {} {} -> {}",
+                green("updated  ", color_enabled),
+                dep.name,
+                old_revision,
+                short_revision(dep)
+            )
+        });
+    }
+    rewire(root, vendored, staged, color_enabled)?;
+    info("");
+    for line in lines {
+        info(&line);
+    }
+    Ok(ExitCode::SUCCESS)
+}
@@ -78,7 +78,15 @@ EOF
 sed -i "" "s|__ACUTEST_CACHE__|$ACUTEST_CACHE|g" "$ACUTEST_BIN/curl"
 chmod +x "$ACUTEST_BIN/curl"
 
-export PATH="$ACUTEST_BIN:$PATH"
+# make test runs `c-init gen-tests`, so put c-init on PATH as an install would
+export PATH="$ACUTEST_BIN:$(dirname "$CINIT"):$PATH"
+
+# Keep the user's c-init config out of the tests
+CONFIG_TMP=$(mktemp -d)
+TMP_DIRS+=("$CONFIG_TMP")
+export XDG_CONFIG_HOME="$CONFIG_TMP"
+unset C_INIT_CC C_INIT_STRICTNESS C_INIT_LINTER_STRICTNESS C_INIT_STD C_INIT_LICENSE \
+  C_INIT_AUTHOR C_INIT_TEST_FRAMEWORK C_INIT_GIT C_INIT_GIT_COMMIT
 
 test_begin() {
   TEST_NAME="$1"
@@ -395,6 +403,888 @@ assert_contains "$LAST_OUT" "ARG:rel_bar"
 assert_file "$PROJ_REL/target/release/proj"
 test_ok
 
+# 13) --lib scaffolds a library and tests link against it
+test_begin "--lib builds static/shared libraries and tests"
+TMPDIR_LIB=$(mktemp -d)
+TMP_DIRS+=("$TMPDIR_LIB")
+PROJ_LIB="$TMPDIR_LIB/mylib"
+run "$CINIT" --lib --cc gcc --no-git "$PROJ_LIB"
+assert_code 0
+assert_file "$PROJ_LIB/include/mylib/mylib.h"
+assert_file "$PROJ_LIB/src/mylib.c"
+assert_missing "$PROJ_LIB/src/main.c"
+assert_contains "$(cat "$PROJ_LIB/tests/test_basic.c")" "mylib_add"
+run make -C "$PROJ_LIB"
+assert_code 0
+assert_file "$PROJ_LIB/target/debug/libmylib.a"
+if [ "$(uname -s)" = "Darwin" ]; then
+  assert_file "$PROJ_LIB/target/debug/libmylib.dylib"
+else
+  assert_file "$PROJ_LIB/target/debug/libmylib.so"
+  assert_file "$PROJ_LIB/target/debug/libmylib.so.0"
+fi
+run make -C "$PROJ_LIB" test
+assert_code 0
+assert_contains "$LAST_OUT" "SUCCESS"
+test_ok
+
+# 14) 'add module' generates a header/source/test triple
+test_begin "'add module' generates a linked module"
+TMPDIR_MOD=$(mktemp -d)
+TMP_DIRS+=("$TMPDIR_MOD")
+PROJ_MOD="$TMPDIR_MOD/proj"
+"$CINIT" --cc gcc --no-git "$PROJ_MOD" > /dev/null
+cd "$PROJ_MOD/src"
+run "$CINIT" add module "Ring Buffer"
+assert_code 0
+cd "$ROOT"
+assert_file "$PROJ_MOD/include/ring_buffer.h"
+assert_file "$PROJ_MOD/src/ring_buffer.c"
+assert_file "$PROJ_MOD/tests/test_ring_buffer.c"
+assert_contains "$(cat "$PROJ_MOD/include/ring_buffer.h")" "#ifndef RING_BUFFER_H"
+run make -C "$PROJ_MOD" test
+assert_code 0
+assert_contains "$LAST_OUT" "ring_buffer_init"
+cd "$PROJ_MOD"
+run "$CINIT" add module ring_buffer
+assert_code 1
+assert_contains "$LAST_ERR" "already exists"
+run "$CINIT" add module vec --guard pragma-once
+assert_code 0
+assert_contains "$(cat "$PROJ_MOD/include/vec.h")" "#pragma once"
+run "$CINIT" add module ../escape
+assert_code 1
+assert_contains "$LAST_ERR" "invalid module name"
+assert_missing "$PROJ_MOD/escape.h"
+run "$CINIT" add module net/http
+assert_code 1
+assert_missing "$PROJ_MOD/tests/test_net"
+# a file that cannot be written leaves none of the module behind
+mv "$PROJ_MOD/tests/generated" "$PROJ_MOD/tests/generated.keep"
+touch "$PROJ_MOD/tests/generated"
+run "$CINIT" add module queue
+assert_code 1
+assert_contains "$LAST_ERR" "no changes were made"
+assert_missing "$PROJ_MOD/include/queue.h"
+assert_missing "$PROJ_MOD/src/queue.c"
+assert_missing "$PROJ_MOD/tests/test_queue.c"
+rm "$PROJ_MOD/tests/generated"
+mv "$PROJ_MOD/tests/generated.keep" "$PROJ_MOD/tests/generated"
+cd "$ROOT"
+test_ok
+
+# 15) --build-system cmake emits CMakeLists.txt instead of a Makefile
+test_begin "--build-system cmake emits CMakeLists.txt"
+TMPDIR_CMAKE=$(mktemp -d)
+TMP_DIRS+=("$TMPDIR_CMAKE")
+PROJ_CMAKE="$TMPDIR_CMAKE/proj"
+run "$CINIT" --build-system cmake --cc gcc -s strictest --no-git "$PROJ_CMAKE"
+assert_code 0
+assert_file "$PROJ_CMAKE/CMakeLists.txt"
+assert_missing "$PROJ_CMAKE/Makefile"
+assert_contains "$(cat "$PROJ_CMAKE/CMakeLists.txt")" "CMAKE_EXPORT_COMPILE_COMMANDS ON"
+assert_contains "$(cat "$PROJ_CMAKE/CMakeLists.txt")" "-Wduplicated-cond"
+# gcc-only warnings apply only when CMake finds gcc
+assert_contains "$(cat "$PROJ_CMAKE/CMakeLists.txt")" 'if(CMAKE_C_COMPILER_ID STREQUAL "GNU")'
+assert_contains "$(cat "$PROJ_CMAKE/CMakeLists.txt")" "add_test("
+if command -v cmake >/dev/null; then
+  run cmake -S "$PROJ_CMAKE" -B "$PROJ_CMAKE/target/debug"
+  assert_code 0
+  run cmake --build "$PROJ_CMAKE/target/debug"
+  assert_code 0
+  run ctest --test-dir "$PROJ_CMAKE/target/debug"
+  assert_code 0
+fi
+test_ok
+
+# 16) --build-system meson emits meson.build and meson_options.txt
+test_begin "--build-system meson emits meson.build"
+TMPDIR_MESON=$(mktemp -d)
+TMP_DIRS+=("$TMPDIR_MESON")
+PROJ_MESON="$TMPDIR_MESON/proj"
+run "$CINIT" --build-system meson --cc gcc -s strictest --no-git "$PROJ_MESON"
+assert_code 0
+assert_file "$PROJ_MESON/meson.build"
+assert_file "$PROJ_MESON/meson_options.txt"
+assert_missing "$PROJ_MESON/Makefile"
+assert_contains "$(cat "$PROJ_MESON/meson.build")" "'-Wduplicated-cond',"
+assert_contains "$(cat "$PROJ_MESON/meson.build")" "test(fs.stem(name), test_exe)"
+assert_contains "$(cat "$PROJ_MESON/meson_options.txt")" "value: 'strictest'"
+if command -v meson >/dev/null; then
+  run env CC=gcc meson setup "$PROJ_MESON/target/debug" "$PROJ_MESON"
+  assert_code 0
+  run meson test -C "$PROJ_MESON/target/debug"
+  assert_code 0
+fi
+test_ok
+
+# 17) compile_commands.json is generated and 'make compdb' reproduces it
+test_begin "compile_commands.json generated and regenerated by make compdb"
+TMPDIR_COMPDB=$(mktemp -d)
+TMP_DIRS+=("$TMPDIR_COMPDB")
+PROJ_COMPDB="$TMPDIR_COMPDB/proj"
+run "$CINIT" --cc gcc --no-git "$PROJ_COMPDB"
+assert_code 0
+assert_file "$PROJ_COMPDB/compile_commands.json"
+assert_contains "$(cat "$PROJ_COMPDB/compile_commands.json")" '"file": "src/main.c"'
+assert_contains "$(cat "$PROJ_COMPDB/compile_commands.json")" '"file": "test_basic.c"'
+GENERATED_COMPDB=$(cat "$PROJ_COMPDB/compile_commands.json")
+run make -C "$PROJ_COMPDB" compdb
+assert_code 0
+[ "$GENERATED_COMPDB" = "$(cat "$PROJ_COMPDB/compile_commands.json")" ] || fail "make compdb output differs"
+# quotes and backslashes in flags are escaped for JSON
+printf '\n%s\n' '-DGREETING="a\b"' >>"$PROJ_COMPDB/compile_flags.txt"
+run make -C "$PROJ_COMPDB" compdb
+assert_code 0
+assert_contains "$(cat "$PROJ_COMPDB/compile_commands.json")" '"-DGREETING=\"a\\b\""'
+if command -v python3 >/dev/null; then
+  run python3 -m json.tool "$PROJ_COMPDB/compile_commands.json"
+  assert_code 0
+fi
+test_ok
+
+# 18) config file defaults, env overrides and CLI precedence
+test_begin "config file provides defaults with CLI > env > config precedence"
+TMPDIR_CFG=$(mktemp -d)
+TMP_DIRS+=("$TMPDIR_CFG")
+run "$CINIT" config set cc gcc
+assert_code 0
+run "$CINIT" config set std c11
+assert_code 0
+run "$CINIT" config set git false
+assert_code 0
+run "$CINIT" config set strictness bogus
+assert_code 1
+assert_contains "$LAST_ERR" "expected loose | strict | strictest"
+run "$CINIT" config get cc
+assert_code 0
+assert_contains "$LAST_OUT" "gcc"
+run "$CINIT" config list
+assert_contains "$LAST_OUT" "std = c11"
+run "$CINIT" "$TMPDIR_CFG/from_config"
+assert_code 0
+assert_contains "$LAST_OUT" "using gcc"
+assert_contains "$(cat "$TMPDIR_CFG/from_config/compile_flags.txt")" "-std=c11"
+assert_missing "$TMPDIR_CFG/from_config/.git"
+run env C_INIT_GIT=true "$CINIT" --no-commit "$TMPDIR_CFG/git_from_env"
+assert_code 0
+assert_dir "$TMPDIR_CFG/git_from_env/.git"
+run "$CINIT" --git --no-commit "$TMPDIR_CFG/git_from_cli"
+assert_code 0
+assert_dir "$TMPDIR_CFG/git_from_cli/.git"
+run "$CINIT" --git --no-git "$TMPDIR_CFG/git_both"
+assert_code 2
+run env C_INIT_STD=c99 "$CINIT" "$TMPDIR_CFG/from_env"
+assert_code 0
+assert_contains "$(cat "$TMPDIR_CFG/from_env/compile_flags.txt")" "-std=c99"
+run env C_INIT_CC=clang "$CINIT" --cc gcc "$TMPDIR_CFG/from_cli"
+assert_code 0
+assert_contains "$LAST_OUT" "using gcc"
+run "$CINIT" config unset cc
+run "$CINIT" config unset std
+run "$CINIT" config unset git
+run "$CINIT" config get cc
+assert_code 1
+test_ok
+
+# 19) template directories override embedded files and add new ones
+test_begin "--template and config templates override and extend the generated tree"
+TMPDIR_TPL=$(mktemp -d)
+TMP_DIRS+=("$TMPDIR_TPL")
+mkdir -p "$TMPDIR_TPL/mine/src" "$TMPDIR_TPL/mine/docs" "$XDG_CONFIG_HOME/c-init/templates/default"
+printf '#include <stdio.h>\n\nint main(void) {\n  puts("custom {{ project_name }}");\n  return 0;\n}\n' >"$TMPDIR_TPL/mine/src/main.c"
+printf '# {{ name }} notes\n{%% if kind == "lib" %%}\nlibrary only\n{%% endif %%}\n' >"$TMPDIR_TPL/mine/docs/{{ name }}.md"
+printf 'Checks: "-*"\n' >"$XDG_CONFIG_HOME/c-init/templates/default/.clang-tidy"
+run "$CINIT" --cc gcc --no-git --template "$TMPDIR_TPL/mine" "$TMPDIR_TPL/tpl_proj"
+assert_code 0
+assert_contains "$(cat "$TMPDIR_TPL/tpl_proj/src/main.c")" 'puts("custom tpl_proj")'
+assert_file "$TMPDIR_TPL/tpl_proj/docs/tpl_proj.md"
+assert_contains "$(cat "$TMPDIR_TPL/tpl_proj/docs/tpl_proj.md")" "# tpl_proj notes"
+if [[ "$(cat "$TMPDIR_TPL/tpl_proj/docs/tpl_proj.md")" == *"library only"* ]]; then
+  fail "LIB section kept in a binary project"
+fi
+assert_contains "$(cat "$TMPDIR_TPL/tpl_proj/.clang-tidy")" 'Checks: "-*"'
+run make -C "$TMPDIR_TPL/tpl_proj" run
+assert_code 0
+assert_contains "$LAST_OUT" "custom tpl_proj"
+run "$CINIT" --cc gcc --no-git --template does-not-exist "$TMPDIR_TPL/missing"
+assert_code 1
+assert_contains "$LAST_ERR" "template 'does-not-exist' not found"
+rm -rf "$XDG_CONFIG_HOME/c-init/templates"
+test_ok
+
+# 20) templates are rendered with conditionals and includes; errors name file and line
+test_begin "template engine renders includes and reports malformed templates"
+TMPDIR_JJ=$(mktemp -d)
+TMP_DIRS+=("$TMPDIR_JJ")
+mkdir -p "$TMPDIR_JJ/extend" "$TMPDIR_JJ/broken"
+printf '{%% include "builtin/README.md" %%}\n{%% for f in flags.strict %%}\n- {{ f }}\n{%% endfor %%}\n' >"$TMPDIR_JJ/extend/README.md"
+printf 'ok\n{%% if features.tests %%}\nunclosed\n' >"$TMPDIR_JJ/broken/Makefile"
+run "$CINIT" --cc gcc --no-git --template "$TMPDIR_JJ/extend" "$TMPDIR_JJ/extended"
+assert_code 0
+assert_contains "$(cat "$TMPDIR_JJ/extended/README.md")" "# extended"
+assert_contains "$(cat "$TMPDIR_JJ/extended/README.md")" "- -Wpedantic"
+run "$CINIT" --cc gcc --no-git --template "$TMPDIR_JJ/broken" "$TMPDIR_JJ/broken_proj"
+assert_code 1
+assert_contains "$LAST_ERR" "broken/Makefile:"
+assert_contains "$LAST_ERR" "syntax error"
+test_ok
+
+# 21) --dry-run prints the planned tree (and diffs with --force) without writing
+test_begin "--dry-run previews files without touching disk"
+TMPDIR_DRY=$(mktemp -d)
+TMP_DIRS+=("$TMPDIR_DRY")
+run "$CINIT" --cc gcc --dry-run "$TMPDIR_DRY/preview"
+assert_code 0
+assert_missing "$TMPDIR_DRY/preview"
+assert_contains "$LAST_OUT" "main.c"
+assert_contains "$LAST_OUT" "acutest.h"
+assert_contains "$LAST_OUT" "Nothing was written"
+run "$CINIT" --cc gcc --no-git "$TMPDIR_DRY/existing"
+assert_code 0
+printf '/* local change */\n' >>"$TMPDIR_DRY/existing/src/main.c"
+BEFORE_DRY=$(cat "$TMPDIR_DRY/existing/src/main.c")
+run "$CINIT" --cc gcc --no-git --force --dry-run=diff "$TMPDIR_DRY/existing"
+assert_code 0
+assert_contains "$LAST_OUT" "overwrite"
+assert_contains "$LAST_OUT" "--- a/src/main.c"
+assert_contains "$LAST_OUT" "-/* local change */"
+[ "$BEFORE_DRY" = "$(cat "$TMPDIR_DRY/existing/src/main.c")" ] || fail "dry run modified src/main.c"
+test_ok
+
+# 22) a failed run leaves an existing directory exactly as it was
+test_begin "failed generation rolls back every change"
+TMPDIR_TX=$(mktemp -d)
+TMP_DIRS+=("$TMPDIR_TX")
+PROJ_TX="$TMPDIR_TX/proj"
+run "$CINIT" --cc gcc --no-git "$PROJ_TX"
+assert_code 0
+printf 'my readme\n' >"$PROJ_TX/README.md"
+printf '/* mine */\n' >>"$PROJ_TX/src/main.c"
+rm -rf "$PROJ_TX/tests" "$PROJ_TX/Makefile"
+# a file where tests/ should be makes writing tests/test_basic.c fail
+printf 'not a directory\n' >"$PROJ_TX/tests"
+BEFORE_TX=$(cd "$PROJ_TX" && ls -A && cat README.md src/main.c)
+run "$CINIT" --cc gcc --no-git --force "$PROJ_TX"
+assert_code 1
+assert_contains "$LAST_ERR" "no changes were made"
+[ "$BEFORE_TX" = "$(cd "$PROJ_TX" && ls -A && cat README.md src/main.c)" ] || fail "directory changed after failed run"
+assert_missing "$PROJ_TX/Makefile"
+test_ok
+
+# 23) --on-conflict decides per file what happens to existing files
+test_begin "--on-conflict skips, backs up, merges or prompts per file"
+TMPDIR_OC=$(mktemp -d)
+TMP_DIRS+=("$TMPDIR_OC")
+PROJ_OC="$TMPDIR_OC/proj"
+run "$CINIT" --cc gcc --no-git "$PROJ_OC"
+assert_code 0
+printf 'my readme\n' >"$PROJ_OC/README.md"
+printf '/* mine */\n' >>"$PROJ_OC/src/main.c"
+rm "$PROJ_OC/Makefile"
+cp -r "$PROJ_OC" "$TMPDIR_OC/original"
+run "$CINIT" --cc gcc --no-git --on-conflict skip "$PROJ_OC"
+assert_code 0
+assert_contains "$LAST_OUT" "skipped     README.md"
+assert_contains "$LAST_OUT" "created     Makefile"
+assert_contains "$LAST_OUT" "unchanged   tests/test_basic.c"
+assert_contains "$(cat "$PROJ_OC/README.md")" "my readme"
+assert_file "$PROJ_OC/Makefile"
+run "$CINIT" --cc gcc --no-git --on-conflict backup "$PROJ_OC"
+assert_code 0
+assert_contains "$LAST_OUT" "backed up   README.md -> README.md.orig"
+assert_contains "$(cat "$PROJ_OC/README.md.orig")" "my readme"
+assert_contains "$(cat "$PROJ_OC/README.md")" "# proj"
+rm -rf "$PROJ_OC" && cp -r "$TMPDIR_OC/original" "$PROJ_OC"
+run "$CINIT" --cc gcc --no-git --on-conflict merge "$PROJ_OC"
+assert_code 0
+assert_contains "$LAST_OUT" "conflict    src/main.c"
+assert_contains "$(cat "$PROJ_OC/src/main.c")" "<<<<<<< existing"
+assert_contains "$(cat "$PROJ_OC/src/main.c")" "/* mine */"
+rm -rf "$PROJ_OC" && cp -r "$TMPDIR_OC/original" "$PROJ_OC"
+# conflicts are asked in plan order: src/main.c, then README.md
+run "$CINIT" --cc gcc --no-git --on-conflict prompt "$PROJ_OC" <<< "$(printf "1\n0\n")"
+assert_code 0
+assert_contains "$LAST_OUT" "overwritten src/main.c"
+assert_contains "$LAST_OUT" "skipped     README.md"
+assert_contains "$(cat "$PROJ_OC/README.md")" "my readme"
+if [[ "$(cat "$PROJ_OC/src/main.c")" == *"mine"* ]]; then
+  fail "src/main.c was not overwritten"
+fi
+test_ok
+
+# 24) adopt wraps an existing codebase without touching its sources
+test_begin "adopt generates build files around an existing layout"
+TMPDIR_AD=$(mktemp -d)
+TMP_DIRS+=("$TMPDIR_AD")
+PROJ_AD="$TMPDIR_AD/legacy"
+mkdir -p "$PROJ_AD/lib" "$PROJ_AD/app" "$PROJ_AD/include/util"
+printf '#ifndef UTIL_H\n#define UTIL_H\nint util_add(int a, int b);\n#endif\n' >"$PROJ_AD/include/util/util.h"
+printf '#include "util/util.h"\n\nint util_add(int a, int b) { return a + b; }\n' >"$PROJ_AD/lib/util.c"
+printf '#include <stdio.h>\n\n#include "util/util.h"\n\nint\nmain (void)\n{\n  printf ("%%d\\n", util_add (2, 3));\n  return 0;\n}\n' >"$PROJ_AD/app/cli.c"
+printf 'all:\n\tcc app/*.c lib/*.c -Iinclude\n' >"$PROJ_AD/Makefile"
+# a vendored library is not part of the project's own sources
+mkdir -p "$PROJ_AD/deps/stb"
+printf 'int stb_unused(void) { return 0; }\n' >"$PROJ_AD/deps/stb/stb.c"
+SOURCES_AD=$(cd "$PROJ_AD" && cat lib/util.c app/cli.c include/util/util.h)
+run "$CINIT" adopt --cc gcc "$PROJ_AD"
+assert_code 0
+assert_contains "$LAST_OUT" "as an executable (sources: app lib; headers: include)"
+assert_contains "$LAST_OUT" "backed up   Makefile -> Makefile.orig"
+assert_contains "$(cat "$PROJ_AD/Makefile.orig")" "cc app/*.c lib/*.c"
+assert_contains "$(cat "$PROJ_AD/Makefile")" "SRC_DIR := app lib"
+assert_contains "$(cat "$PROJ_AD/Makefile")" "filter-out \$(OBJ_DIR)/cli.o"
+assert_contains "$(cat "$PROJ_AD/compile_flags.txt")" "-Iinclude"
+assert_file "$PROJ_AD/tests/test_basic.c"
+assert_missing "$PROJ_AD/README.md"
+assert_missing "$PROJ_AD/src"
+[ "$SOURCES_AD" = "$(cd "$PROJ_AD" && cat lib/util.c app/cli.c include/util/util.h)" ] || fail "adopt changed existing sources"
+run make -C "$PROJ_AD" -s
+assert_code 0
+run "$PROJ_AD/target/debug/legacy"
+assert_contains "$LAST_OUT" "5"
+run make -C "$PROJ_AD" -s test
+assert_code 0
+# add module follows the adopted layout, from anywhere in the project
+cd "$PROJ_AD/app"
+run "$CINIT" add module ring
+assert_code 0
+assert_file "$PROJ_AD/include/ring.h"
+assert_file "$PROJ_AD/app/ring.c"
+assert_missing "$PROJ_AD/src"
+cd "$ROOT"
+run make -C "$PROJ_AD" -s test
+assert_code 0
+assert_contains "$LAST_OUT" "Test ring_init"
+# no main() anywhere: a library
+PROJ_AD_LIB="$TMPDIR_AD/flat"
+mkdir -p "$PROJ_AD_LIB"
+printf 'int flat(void);\n' >"$PROJ_AD_LIB/flat.h"
+printf '#include "flat.h"\nint flat(void) { return 1; }\n' >"$PROJ_AD_LIB/flat.c"
+run "$CINIT" adopt --cc gcc --no-tests "$PROJ_AD_LIB"
+assert_code 0
+assert_contains "$LAST_OUT" "as a library (sources: .; headers: .)"
+run make -C "$PROJ_AD_LIB" -s
+assert_code 0
+assert_file "$PROJ_AD_LIB/target/debug/libflat.a"
+# several main()s: an error naming them, until --src-dir narrows the build to one
+PROJ_AD_MAINS="$TMPDIR_AD/tools"
+mkdir -p "$PROJ_AD_MAINS/one" "$PROJ_AD_MAINS/two"
+printf 'int main(void) { return 0; }\n' >"$PROJ_AD_MAINS/one/one.c"
+printf 'int main(void) { return 0; }\n' >"$PROJ_AD_MAINS/two/two.c"
+run "$CINIT" adopt --cc gcc --no-tests "$PROJ_AD_MAINS"
+assert_code 1
+assert_contains "$LAST_ERR" "main() is defined in one/one.c, two/two.c"
+assert_contains "$LAST_ERR" "--src-dir"
+assert_missing "$PROJ_AD_MAINS/Makefile"
+run "$CINIT" adopt --cc gcc --no-tests --src-dir one "$PROJ_AD_MAINS"
+assert_code 0
+test_ok
+
+# 25) upgrade re-applies changed templates and merges local edits
+test_begin "upgrade updates untouched files and three-way merges edited ones"
+TMPDIR_UP=$(mktemp -d)
+TMP_DIRS+=("$TMPDIR_UP")
+TPL_UP="$TMPDIR_UP/tpl"
+PROJ_UP="$TMPDIR_UP/proj"
+mkdir -p "$TPL_UP"
+printf '{%% include "builtin/Makefile" %%}\n# footer v1\n' >"$TPL_UP/Makefile"
+printf 'one\ntwo\nthree\nfour\nfive\n' >"$TPL_UP/NOTES.txt"
+printf 'v1\n' >"$TPL_UP/VERSION"
+run "$CINIT" --cc gcc --no-git --template "$TPL_UP" "$PROJ_UP"
+assert_code 0
+assert_file "$PROJ_UP/.c-init/manifest.toml"
+assert_contains "$(cat "$PROJ_UP/.c-init/manifest.toml")" "Makefile = \"sha256:"
+# machine-specific files are left out of the manifest and base copies
+if grep -q "compile_flags.txt\|compile_commands.json" "$PROJ_UP/.c-init/manifest.toml"; then
+  fail "the manifest records files with this machine's paths"
+fi
+assert_missing "$PROJ_UP/.c-init/base/compile_flags.txt"
+assert_missing "$PROJ_UP/.c-init/base/tests/compile_flags.txt"
+# local edits: one clean, one overlapping the template change
+sed -i '1i # my local tweak' "$PROJ_UP/Makefile"
+sed -i 's/^two$/two (mine)/' "$PROJ_UP/NOTES.txt"
+printf '{%% include "builtin/Makefile" %%}\n# footer v2\n' >"$TPL_UP/Makefile"
+printf 'one\ntwo (template)\nthree\nfour\nfive (template)\n' >"$TPL_UP/NOTES.txt"
+printf 'v2\n' >"$TPL_UP/VERSION"
+run bash -c "cd '$PROJ_UP/src' && '$CINIT' upgrade"
+assert_code 0
+assert_contains "$LAST_OUT" "merged      Makefile"
+assert_contains "$LAST_OUT" "conflict    NOTES.txt"
+assert_contains "$LAST_OUT" "overwritten VERSION"
+assert_contains "$(cat "$PROJ_UP/Makefile")" "# my local tweak"
+assert_contains "$(cat "$PROJ_UP/Makefile")" "# footer v2"
+assert_contains "$(cat "$PROJ_UP/NOTES.txt")" "<<<<<<< existing"
+assert_contains "$(cat "$PROJ_UP/NOTES.txt")" "five (template)"
+assert_contains "$(cat "$PROJ_UP/VERSION")" "v2"
+# edits to files whose template did not change are kept as they are
+printf 'my readme\n' >"$PROJ_UP/README.md"
+run "$CINIT" upgrade "$PROJ_UP"
+assert_code 0
+assert_contains "$(cat "$PROJ_UP/README.md")" "my readme"
+run bash -c "cd '$TMPDIR_UP' && '$CINIT' upgrade"
+assert_code 1
+assert_contains "$LAST_ERR" "no .c-init/manifest.toml found"
+test_ok
+
+# 26) doctor reports the toolchain and fails when the build can't work
+test_begin "doctor checks the compiler, build tool and optional tools"
+TMPDIR_DR=$(mktemp -d)
+TMP_DIRS+=("$TMPDIR_DR")
+PROJ_DR="$TMPDIR_DR/proj"
+run "$CINIT" --cc gcc --no-git "$PROJ_DR"
+assert_code 0
+# a stand-in gcovr, for make coverage's HTML report
+mkdir -p "$TMPDIR_DR/bin"
+printf '#!/bin/sh\necho "gcovr 7.2"\n' >"$TMPDIR_DR/bin/gcovr"
+chmod +x "$TMPDIR_DR/bin/gcovr"
+run bash -c "cd '$PROJ_DR/src' && PATH='$TMPDIR_DR/bin':\"\$PATH\" '$CINIT' doctor"
+assert_code 0
+assert_contains "$LAST_OUT" "Checking the toolchain for $PROJ_DR (make)"
+assert_contains "$LAST_OUT" "✔ gcc"
+assert_contains "$LAST_OUT" "✔ compile"
+assert_contains "$LAST_OUT" "✔ make"
+assert_contains "$LAST_OUT" "✔ gcovr"
+assert_contains "$LAST_OUT" "clang-tidy"
+assert_contains "$LAST_OUT" "0 problem(s)"
+if ! command -v gcovr >/dev/null 2>&1 && ! command -v lcov >/dev/null 2>&1; then
+  run bash -c "cd '$PROJ_DR' && '$CINIT' doctor"
+  assert_code 1
+  assert_contains "$LAST_OUT" "✘ gcovr"
+  assert_contains "$LAST_OUT" "make coverage needs one for its HTML report"
+fi
+run env PATH=/nonexistent "$CINIT" doctor --cc gcc
+assert_code 1
+assert_contains "$LAST_OUT" "✘ gcc"
+assert_contains "$LAST_OUT" "✘ make"
+test_ok
+
+# 27) warning flags the compiler rejects are left out unless --no-probe
+test_begin "flags are probed against the compiler"
+TMPDIR_PR=$(mktemp -d)
+TMP_DIRS+=("$TMPDIR_PR")
+mkdir -p "$TMPDIR_PR/bin"
+# a gcc that predates -Wduplicated-branches
+cat >"$TMPDIR_PR/bin/gcc" <<'SH'
+#!/bin/sh
+for arg in "$@"; do
+  if [ "$arg" = "-Wduplicated-branches" ]; then
+    echo "gcc: error: unrecognized command-line option '$arg'" >&2
+    exit 1
+  fi
+done
+exec /usr/bin/gcc "$@"
+SH
+chmod +x "$TMPDIR_PR/bin/gcc"
+run env PATH="$TMPDIR_PR/bin:$PATH" "$CINIT" --cc gcc -s strictest --no-git "$TMPDIR_PR/probed"
+assert_code 0
+assert_contains "$LAST_ERR" "gcc does not support -Wduplicated-branches"
+assert_contains "$(cat "$TMPDIR_PR/probed/compile_flags.txt")" "-Wduplicated-cond"
+if grep -q -- "-Wduplicated-branches" "$TMPDIR_PR/probed/compile_flags.txt"; then
+  fail "rejected flag kept in compile_flags.txt"
+fi
+run env PATH="$TMPDIR_PR/bin:$PATH" make -C "$TMPDIR_PR/probed" -s
+assert_code 0
+run env PATH="$TMPDIR_PR/bin:$PATH" "$CINIT" --cc gcc -s strictest --no-git --no-probe "$TMPDIR_PR/unprobed"
+assert_code 0
+assert_contains "$(cat "$TMPDIR_PR/unprobed/compile_flags.txt")" "-Wduplicated-branches"
+test_ok
+
+# 28) --std selects the language standard; templates stay valid C89
+test_begin "--std sets the standard and rejects ones the compiler lacks"
+TMPDIR_STD=$(mktemp -d)
+TMP_DIRS+=("$TMPDIR_STD")
+run "$CINIT" --cc gcc --std c89 -s strictest --no-git "$TMPDIR_STD/old"
+assert_code 0
+assert_contains "$(head -n 1 "$TMPDIR_STD/old/compile_flags.txt")" "-std=c89"
+assert_contains "$(cat "$TMPDIR_STD/old/tests/compile_flags.txt")" "-std=c89"
+assert_contains "$(cat "$TMPDIR_STD/old/.c-init/manifest.toml")" "std = \"c89\""
+run make -C "$TMPDIR_STD/old" -s test
+assert_code 0
+mkdir -p "$TMPDIR_STD/bin"
+# a gcc that predates C23
+cat >"$TMPDIR_STD/bin/gcc" <<'SH'
+#!/bin/sh
+for arg in "$@"; do
+  if [ "$arg" = "-std=c2x" ]; then
+    echo "gcc: error: unrecognized command-line option '$arg'" >&2
+    exit 1
+  fi
+done
+exec /usr/bin/gcc "$@"
+SH
+chmod +x "$TMPDIR_STD/bin/gcc"
+run env PATH="$TMPDIR_STD/bin:$PATH" "$CINIT" --cc gcc --std c23 --no-git "$TMPDIR_STD/new"
+assert_code 1
+assert_contains "$LAST_ERR" "gcc does not support -std=c2x (--std c23)"
+assert_missing "$TMPDIR_STD/new"
+run env PATH="$TMPDIR_STD/bin:$PATH" "$CINIT" --cc gcc --std c17 --no-git "$TMPDIR_STD/new"
+assert_code 0
+test_ok
+
+# 29) --cc takes tcc, zig, icx or any command, detecting its family
+test_begin "--cc accepts more compilers and arbitrary commands"
+TMPDIR_CC=$(mktemp -d)
+TMP_DIRS+=("$TMPDIR_CC")
+run "$CINIT" --cc cc --no-git "$TMPDIR_CC/system"
+assert_code 0
+assert_contains "$(head -n 1 "$TMPDIR_CC/system/Makefile")" "CC      := cc"
+assert_contains "$(cat "$TMPDIR_CC/system/.c-init/manifest.toml")" "cc_command = \"cc\""
+run make -C "$TMPDIR_CC/system" -s test
+assert_code 0
+run "$CINIT" --cc tcc --no-probe --no-git "$TMPDIR_CC/tiny"
+assert_code 0
+assert_contains "$(head -n 1 "$TMPDIR_CC/tiny/Makefile")" "CC      := tcc"
+assert_contains "$(cat "$TMPDIR_CC/tiny/compile_flags.txt")" "-Wwrite-strings"
+if grep -q -- "-Wpedantic" "$TMPDIR_CC/tiny/compile_flags.txt"; then
+  fail "tcc got gcc/clang warning flags"
+fi
+run "$CINIT" --cc zig --no-probe --no-git "$TMPDIR_CC/zig"
+assert_code 0
+assert_contains "$(head -n 1 "$TMPDIR_CC/zig/Makefile")" "CC      := zig cc"
+assert_contains "$(cat "$TMPDIR_CC/zig/compile_commands.json")" "\"zig\", \"cc\""
+# a clang under an unusual name is recognised from --version
+mkdir -p "$TMPDIR_CC/bin"
+printf '#!/bin/sh\necho "clang version 18.1.8"\n' >"$TMPDIR_CC/bin/cc-18"
+chmod +x "$TMPDIR_CC/bin/cc-18"
+run "$CINIT" --cc "$TMPDIR_CC/bin/cc-18" -s strictest --no-probe --no-git "$TMPDIR_CC/custom"
+assert_code 0
+assert_contains "$(cat "$TMPDIR_CC/custom/.c-init/manifest.toml")" "cc = \"clang\""
+assert_contains "$(cat "$TMPDIR_CC/custom/compile_flags.txt")" "-Wstrict-overflow=5"
+assert_contains "$(head -n 1 "$TMPDIR_CC/custom/Makefile")" "$TMPDIR_CC/bin/cc-18"
+test_ok
+
+# 30) --target cross-compiles into target/<triple>/ and runs foreign binaries via qemu
+test_begin "--target picks a cross compiler and a per-triple build layout"
+TMPDIR_X=$(mktemp -d)
+TMP_DIRS+=("$TMPDIR_X")
+run "$CINIT" --cc gcc --target aarch64-linux-gnu --no-probe --no-git "$TMPDIR_X/arm"
+assert_code 0
+assert_contains "$(cat "$TMPDIR_X/arm/Makefile")" "CC      := aarch64-linux-gnu-gcc"
+assert_contains "$(cat "$TMPDIR_X/arm/Makefile")" "BUILD_DIR := target/\$(TRIPLE)/release"
+assert_contains "$(cat "$TMPDIR_X/arm/Makefile")" "RUNNER := qemu-aarch64 -L /usr/aarch64-linux-gnu"
+run "$CINIT" --cc clang --target aarch64-linux-musl --sysroot /opt/musl --no-probe --no-git "$TMPDIR_X/musl"
+assert_code 0
+assert_contains "$(cat "$TMPDIR_X/musl/Makefile")" "CC      := clang --target=aarch64-linux-musl --sysroot=/opt/musl"
+assert_contains "$(cat "$TMPDIR_X/musl/Makefile")" "qemu-aarch64 -L /opt/musl"
+run "$CINIT" --target aarch64 --no-git "$TMPDIR_X/bad"
+assert_code 1
+assert_contains "$LAST_ERR" "invalid target 'aarch64'"
+run "$CINIT" --cc tcc --target aarch64-linux-gnu --no-git "$TMPDIR_X/bad"
+assert_code 1
+assert_contains "$LAST_ERR" "tcc cannot cross-compile"
+# the host's own triple builds natively, without qemu
+HOST_TRIPLE="$(uname -m)-linux-gnu"
+if command -v "$HOST_TRIPLE-gcc" >/dev/null; then
+  run "$CINIT" --cc gcc --target "$HOST_TRIPLE" --no-git "$TMPDIR_X/host"
+  assert_code 0
+  run make -C "$TMPDIR_X/host" -s test
+  assert_code 0
+  assert_dir "$TMPDIR_X/host/target/$HOST_TRIPLE/debug"
+  run make -C "$TMPDIR_X/host" -s run
+  assert_contains "$LAST_OUT" "Hello from host!"
+fi
+test_ok
+
+# 31) include paths come from the compiler and pkg-config, not a fixed list
+test_begin "--dep adds pkg-config include paths; no hard-coded Homebrew paths"
+TMPDIR_INC=$(mktemp -d)
+TMP_DIRS+=("$TMPDIR_INC")
+mkdir -p "$TMPDIR_INC/pc" "$TMPDIR_INC/fake/include"
+printf '#define FAKE_ANSWER 42\n' >"$TMPDIR_INC/fake/include/fake.h"
+printf 'Name: fake\nDescription: fake\nVersion: 1.0\nCflags: -I%s/fake/include -I%s/missing\n' \
+  "$TMPDIR_INC" "$TMPDIR_INC" >"$TMPDIR_INC/pc/fake.pc"
+PKG_CONFIG_PATH="$TMPDIR_INC/pc" run "$CINIT" --cc gcc --dep fake --no-git "$TMPDIR_INC/app"
+assert_code 0
+assert_contains "$(cat "$TMPDIR_INC/app/compile_flags.txt")" "-isystem$TMPDIR_INC/fake/include"
+assert_contains "$(cat "$TMPDIR_INC/app/tests/compile_flags.txt")" "-isystem$TMPDIR_INC/fake/include"
+assert_contains "$(cat "$TMPDIR_INC/app/Makefile")" "#   $TMPDIR_INC/fake/include (pkg-config --cflags fake)"
+assert_contains "$(cat "$TMPDIR_INC/app/.c-init/manifest.toml")" "deps = [\"fake\"]"
+if grep -q "$TMPDIR_INC/missing" "$TMPDIR_INC/app/compile_flags.txt"; then
+  fail "a directory that does not exist was added"
+fi
+printf '#include <fake.h>\nint fake_answer(void);\nint fake_answer(void) { return FAKE_ANSWER; }\n' \
+  >"$TMPDIR_INC/app/src/fake_answer.c"
+PKG_CONFIG_PATH="$TMPDIR_INC/pc" run make -C "$TMPDIR_INC/app" -s
+assert_code 0
+run "$CINIT" --cc gcc --dep c-init-no-such-package --no-git "$TMPDIR_INC/bad"
+assert_code 1
+assert_contains "$LAST_ERR" "pkg-config does not know package 'c-init-no-such-package'"
+run "$CINIT" --cc clang --no-probe --no-git "$TMPDIR_INC/clang"
+assert_code 0
+if grep -q "homebrew" "$TMPDIR_INC/clang/compile_flags.txt"; then
+  fail "clang project still hard-codes Homebrew include paths"
+fi
+test_ok
+
+# 32) add dep records a pkg-config package and wires it into the build
+test_begin "add dep links a system library through PKGS"
+TMPDIR_DEP=$(mktemp -d)
+TMP_DIRS+=("$TMPDIR_DEP")
+mkdir -p "$TMPDIR_DEP/pc" "$TMPDIR_DEP/answer/include" "$TMPDIR_DEP/answer/lib"
+printf 'int answer(void);\n' >"$TMPDIR_DEP/answer/include/answer.h"
+printf 'int answer(void);\nint answer(void) { return 42; }\n' >"$TMPDIR_DEP/answer.c"
+gcc -c "$TMPDIR_DEP/answer.c" -o "$TMPDIR_DEP/answer.o"
+ar rcs "$TMPDIR_DEP/answer/lib/libanswer.a" "$TMPDIR_DEP/answer.o"
+printf 'Name: answer\nDescription: answer\nVersion: 4.2\nCflags: -I%s/answer/include\nLibs: -L%s/answer/lib -lanswer\n' \
+  "$TMPDIR_DEP" "$TMPDIR_DEP" >"$TMPDIR_DEP/pc/answer.pc"
+export PKG_CONFIG_PATH="$TMPDIR_DEP/pc"
+run "$CINIT" --cc gcc --no-git "$TMPDIR_DEP/app"
+assert_code 0
+assert_contains "$(cat "$TMPDIR_DEP/app/Makefile")" "PKGS :="
+printf '#include <answer.h>\n#include <stdio.h>\n\nint main(void) {\n  printf("answer %%d\\n", answer());\n  return 0;\n}\n' \
+  >"$TMPDIR_DEP/app/src/main.c"
+run sh -c "cd '$TMPDIR_DEP/app' && '$CINIT' add dep answer"
+assert_code 0
+assert_contains "$LAST_OUT" "Added dependency 'answer'"
+assert_contains "$(cat "$TMPDIR_DEP/app/Makefile")" "PKGS := answer"
+assert_contains "$(cat "$TMPDIR_DEP/app/compile_flags.txt")" "-isystem$TMPDIR_DEP/answer/include"
+assert_contains "$(cat "$TMPDIR_DEP/app/src/main.c")" "answer()"
+run make -C "$TMPDIR_DEP/app" -s run
+assert_code 0
+assert_contains "$LAST_OUT" "answer 42"
+run make -C "$TMPDIR_DEP/app" -s test
+assert_code 0
+run sh -c "cd '$TMPDIR_DEP/app' && '$CINIT' add dep answer"
+assert_code 0
+assert_contains "$LAST_OUT" "already a dependency"
+run sh -c "cd '$TMPDIR_DEP/app' && '$CINIT' add dep c-init-no-such-package"
+assert_code 1
+assert_contains "$LAST_ERR" "install its development package"
+if grep -q "c-init-no-such-package" "$TMPDIR_DEP/app/.c-init/manifest.toml"; then
+  fail "a missing package was recorded"
+fi
+run sh -c "cd '$TMPDIR_DEP/app' && '$CINIT' doctor --cc gcc"
+assert_contains "$LAST_OUT" "answer"
+assert_contains "$LAST_OUT" "4.2"
+unset PKG_CONFIG_PATH
+test_ok
+
+# 33) vendor copies libraries in, pins them in c-init.lock and detects tampering
+test_begin "vendor add/verify/update manage pinned single-header and source libraries"
+TMPDIR_VEN=$(mktemp -d)
+TMP_DIRS+=("$TMPDIR_VEN")
+mkdir -p "$TMPDIR_VEN/mini/include" "$TMPDIR_VEN/mini/src"
+printf '#ifndef MINI_H\n#define MINI_H\nint mini_add(int a, int b);\n#endif\n' >"$TMPDIR_VEN/mini/include/mini.h"
+# unused variable: vendored sources are built without the project's -Werror warnings
+printf '#include "mini.h"\nint mini_add(int a, int b) { int unused; return a + b; }\n' >"$TMPDIR_VEN/mini/src/mini.c"
+git -C "$TMPDIR_VEN/mini" init -q
+git -C "$TMPDIR_VEN/mini" add -A
+git -C "$TMPDIR_VEN/mini" -c user.name=t -c user.email=t@example.com commit -qm v1
+FIRST_REV=$(git -C "$TMPDIR_VEN/mini" rev-parse HEAD)
+printf '#define SQUARE(x) ((x) * (x))\n' >"$TMPDIR_VEN/square.h"
+run "$CINIT" --cc gcc --no-git "$TMPDIR_VEN/app"
+assert_code 0
+cd "$TMPDIR_VEN/app"
+run "$CINIT" vendor add mini --from "$TMPDIR_VEN/mini"
+assert_code 0
+assert_file "$TMPDIR_VEN/app/deps/mini/include/mini.h"
+assert_contains "$(cat c-init.lock)" "revision = \"$FIRST_REV\""
+assert_contains "$(cat compile_flags.txt)" "-isystemdeps/mini/include"
+assert_contains "$(cat tests/compile_flags.txt)" "-isystem../deps/mini/include"
+assert_contains "$(cat Makefile)" "VENDOR_SOURCES := deps/mini/src/mini.c"
+run "$CINIT" vendor add square --from ../square.h --test
+assert_code 0
+assert_file "$TMPDIR_VEN/app/tests/test-deps/square/square.h"
+# a relative --from is recorded relative to the project, an absolute one as given
+assert_contains "$(cat c-init.lock)" "source = \"../square.h\""
+assert_contains "$(cat c-init.lock)" "source = \"$TMPDIR_VEN/mini\""
+assert_contains "$(cat tests/compile_flags.txt)" "-isystem../tests/test-deps/square"
+if grep -q "square" compile_flags.txt; then
+  fail "a test-only dependency reached the app flags"
+fi
+run "$CINIT" vendor add mini --from "$TMPDIR_VEN/mini"
+assert_code 1
+assert_contains "$LAST_ERR" "already vendored"
+# a failure while regenerating the build files writes nothing, lockfile included
+cp c-init.lock "$TMPDIR_VEN/c-init.lock"
+cp .c-init/manifest.toml "$TMPDIR_VEN/manifest.toml"
+sed -i 's/^\[options\]$/[options]\ndeps = ["c-init-no-such-package"]/' .c-init/manifest.toml
+run "$CINIT" vendor add other --from "$TMPDIR_VEN/square.h"
+assert_code 1
+assert_missing "$TMPDIR_VEN/app/deps/other"
+if ! cmp -s c-init.lock "$TMPDIR_VEN/c-init.lock"; then
+  fail "a failed vendor add changed c-init.lock"
+fi
+cp "$TMPDIR_VEN/manifest.toml" .c-init/manifest.toml
+run "$CINIT" vendor add other --from "$TMPDIR_VEN/square.h" --rev HEAD
+assert_code 1
+assert_contains "$LAST_ERR" "--rev needs a git repository"
+printf '#include <mini.h>\n#include <stdio.h>\n\nint main(void) {\n  printf("sum %%d\\n", mini_add(2, 3));\n  return 0;\n}\n' >src/main.c
+printf '#include "acutest.h"\n#include <mini.h>\n#include <square.h>\n\nstatic void test_square(void) { TEST_CHECK(SQUARE(mini_add(1, 1)) == 4); }\n\nTEST_LIST = {\n    {"square", test_square},\n    {NULL, NULL},\n};\n' >tests/test_vendor.c
+run make -s run
+assert_code 0
+assert_contains "$LAST_OUT" "sum 5"
+run make -s test
+assert_code 0
+run "$CINIT" vendor verify
+assert_code 0
+assert_contains "$LAST_OUT" "All vendored files match c-init.lock"
+# local edits are reported and protected from update
+echo "/* local edit */" >>deps/mini/src/mini.c
+run "$CINIT" vendor verify
+assert_code 1
+assert_contains "$LAST_OUT" "deps/mini/src/mini.c: modified"
+printf '#ifndef MINI_H\n#define MINI_H\n/* v2 */\nint mini_add(int a, int b);\n#endif\n' >"$TMPDIR_VEN/mini/include/mini.h"
+git -C "$TMPDIR_VEN/mini" -c user.name=t -c user.email=t@example.com commit -qam v2
+run "$CINIT" vendor update mini
+assert_code 1
+assert_contains "$LAST_ERR" "pass --force to discard them"
+run "$CINIT" vendor update mini --force
+assert_code 0
+assert_contains "$LAST_OUT" "updated"
+assert_contains "$(cat deps/mini/include/mini.h)" "v2"
+if grep -q "local edit" deps/mini/src/mini.c; then
+  fail "update --force kept the local edit"
+fi
+if grep -q "$FIRST_REV" c-init.lock; then
+  fail "c-init.lock still pins the old revision"
+fi
+run "$CINIT" vendor verify
+assert_code 0
+run "$CINIT" vendor update mini --rev "$FIRST_REV"
+assert_code 0
+assert_contains "$(cat c-init.lock)" "revision = \"$FIRST_REV\""
+cd src
+run "$CINIT" vendor update square
+assert_code 0
+assert_contains "$LAST_OUT" "unchanged"
+cd "$TMPDIR_VEN/app"
+cd "$ROOT"
+test_ok
+
+test_begin "--test-framework accepts only the embedded acutest"
+TMPDIR_TFW=$(mktemp -d)
+TMP_DIRS+=("$TMPDIR_TFW")
+run "$CINIT" --cc gcc --no-git --test-framework unity "$TMPDIR_TFW/uapp"
+assert_code 2
+assert_missing "$TMPDIR_TFW/uapp"
+run "$CINIT" --cc gcc --no-git --test-framework acutest "$TMPDIR_TFW/app"
+assert_code 0
+assert_file "$TMPDIR_TFW/app/tests/test-deps/acutest.h"
+assert_contains "$(cat "$TMPDIR_TFW/app/.c-init/manifest.toml")" 'test_framework = "acutest"'
+test_ok
+
+test_begin "gen-tests registers acutest tests and fails on unregistered ones"
+TMPDIR_GEN=$(mktemp -d)
+TMP_DIRS+=("$TMPDIR_GEN")
+run "$CINIT" --cc gcc --no-git "$TMPDIR_GEN/app"
+assert_code 0
+assert_contains "$(cat "$TMPDIR_GEN/app/tests/test_basic.c")" '#include "generated/test_basic.h"'
+assert_contains "$(cat "$TMPDIR_GEN/app/tests/generated/test_basic.h")" '{"addition", test_addition},'
+cd "$TMPDIR_GEN/app"
+run "$CINIT" add module ring
+assert_code 0
+assert_contains "$(cat tests/generated/test_ring.h)" '{"ring_init", test_ring_init},'
+# a new test function runs without touching any list
+sed -i '/^\/\* TEST_LIST/i static void test_extra(void) { TEST_CHECK(1); }\n' tests/test_ring.c
+# without c-init, make test refuses to build a test whose generated header may be stale
+run make -s test C_INIT=/nonexistent/c-init
+assert_code 2
+assert_contains "$LAST_ERR" ": /nonexistent/c-init not found to run \`c-init gen-tests\`"
+assert_contains "$LAST_ERR" "install c-init (or set C_INIT to it)"
+run "$CINIT" gen-tests --check
+assert_code 1
+assert_contains "$LAST_ERR" "test_ring.h is out of date"
+run make -s test
+assert_code 0
+assert_contains "$LAST_OUT" "Test extra"
+run "$CINIT" gen-tests --check
+assert_code 0
+# a hand-written TEST_LIST missing a function fails the build
+printf '#include "acutest.h"\n\nstatic void test_a(void) { TEST_CHECK(1); }\nstatic void test_b(void) { TEST_CHECK(1); }\n\nTEST_LIST = {\n    {"a", test_a},\n    {NULL, NULL},\n};\n' >tests/test_hand.c
+run make -s test
+assert_code 2
+assert_contains "$LAST_ERR" "tests/test_hand.c: test_b is not in TEST_LIST"
+run "$CINIT" upgrade
+assert_code 0
+assert_missing "$TMPDIR_GEN/app/tests/generated/test_ring.h.orig"
+assert_contains "$(cat tests/generated/test_ring.h)" '{"extra", test_extra},'
+cd "$ROOT"
+test_ok
+
+test_begin "make test fails on any failing binary and writes JUnit XML or TAP"
+TMPDIR_RES=$(mktemp -d)
+TMP_DIRS+=("$TMPDIR_RES")
+run "$CINIT" --cc gcc --no-git "$TMPDIR_RES/app"
+assert_code 0
+cd "$TMPDIR_RES/app"
+run make -s test FORMAT=junit
+assert_code 0
+assert_contains "$(cat target/test-results.xml)" '<testsuite name="test_basic" tests="2" errors="0" failures="0"'
+# the failing binary runs first; its failure must not be masked by the passing one after it
+printf '#include "acutest.h"\n\nstatic void test_broken(void) { TEST_CHECK(1 == 2); }\n\n#include "generated/test_a_fail.h"\n' >tests/test_a_fail.c
+run make -s test
+assert_code 2
+assert_contains "$LAST_OUT" "FAILED: test_a_fail"
+run make -s test FORMAT=junit
+assert_code 2
+assert_contains "$(cat target/test-results.xml)" "<testsuites>"
+assert_contains "$(cat target/test-results.xml)" '<testsuite name="test_a_fail" tests="1" errors="0" failures="1"'
+assert_contains "$(cat target/test-results.xml)" '<testsuite name="test_basic"'
+run make -s test TEST_FORMAT=tap
+assert_code 2
+assert_contains "$(cat target/test-results.tap)" "not ok 1 - test_a_fail: broken"
+assert_contains "$(cat target/test-results.tap)" "ok 3 - test_basic: tutorial"
+assert_contains "$(cat target/test-results.tap)" "1..3"
+run make -s test FORMAT=xml
+assert_code 2
+assert_contains "$LAST_ERR" "FORMAT must be junit or tap, not 'xml'"
+run env FORMAT=xml make -s test
+assert_code 2
+assert_contains "$LAST_ERR" "FORMAT must be junit or tap, not 'xml'"
+# TEST_FORMAT overrides a FORMAT from the environment
+run env FORMAT=xml make -s test TEST_FORMAT=tap
+assert_code 2
+assert_contains "$(cat target/test-results.tap)" "1..3"
+rm target/test-results.xml
+run env FORMAT=xml TEST_FORMAT=junit make -s test
+assert_code 2
+assert_file target/test-results.xml
+cd "$ROOT"
+test_ok
+
+test_begin "make coverage builds into target/coverage and enforces --coverage-min"
+TMPDIR_COV=$(mktemp -d)
+TMP_DIRS+=("$TMPDIR_COV")
+run "$CINIT" --cc gcc --no-git --lib --coverage-min 50 "$TMPDIR_COV/lib"
+assert_code 0
+assert_contains "$(cat "$TMPDIR_COV/lib/.c-init/manifest.toml")" "coverage_min = 50"
+assert_contains "$(cat "$TMPDIR_COV/lib/Makefile")" "COVERAGE_MIN ?= 50"
+assert_contains "$(cat "$TMPDIR_COV/lib/Makefile")" "CFLAGS_COVERAGE  := -O0 -g --coverage"
+cd "$TMPDIR_COV/lib"
+# without gcovr, or lcov and genhtml, there is no HTML report and make coverage fails
+run make -s coverage GCOVR=/nonexistent/gcovr LCOV=/nonexistent/lcov
+assert_code 2
+assert_contains "$LAST_ERR" "make coverage needs gcovr (pip install gcovr), or lcov and genhtml"
+assert_file "$TMPDIR_COV/lib/target/coverage/lib.gcda"
+assert_missing "$TMPDIR_COV/lib/target/debug"
+# a stand-in gcovr that writes the report and records how it was called
+cat >"$TMPDIR_COV/gcovr" <<'SH'
+#!/bin/sh
+echo "gcovr $*"
+while [ $# -gt 0 ]; do
+  [ "$1" = "--html-details" ] && echo "<html></html>" >"$2"
+  shift
+done
+SH
+chmod +x "$TMPDIR_COV/gcovr"
+run make -s coverage GCOVR="$TMPDIR_COV/gcovr"
+assert_code 0
+assert_contains "$LAST_OUT" "--filter src/ --gcov-executable gcov --object-directory target/coverage"
+assert_contains "$LAST_OUT" "--fail-under-line 50"
+assert_contains "$LAST_OUT" "HTML report: target/coverage/html/index.html"
+assert_file "$TMPDIR_COV/lib/target/coverage/html/index.html"
+cd "$ROOT"
+run "$CINIT" --cc gcc --no-git --no-tests "$TMPDIR_COV/untested"
+assert_code 0
+if grep -q "coverage" "$TMPDIR_COV/untested/Makefile"; then
+  fail "a project without tests got a coverage target"
+fi
+run "$CINIT" --cc gcc --no-git --coverage-min 101 "$TMPDIR_COV/bad"
+assert_code 2
+test_ok
+
 if [ "$FAIL_COUNT" -ne 0 ]; then
   exit 1
 fi